@@ -3,13 +3,11 @@ extern crate log;
 
 use std::convert::{Into, TryInto};
 use std::env;
-use std::fs::read_to_string;
+use std::fs::{read_to_string, write};
 use std::io::{self, Read};
 use std::path::PathBuf;
 
-use clap_log_flag;
-use clap_verbosity_flag;
-use nest::{Schema, Store, Value};
+use nest::{Change, Missing, Schema, Store, Value};
 use serde_json as json;
 use structopt::StructOpt;
 
@@ -35,11 +33,57 @@ struct Cli {
 enum Command {
     #[structopt(name = "get")]
     /// Get value from Nest.
-    Get { path: String },
+    Get {
+        path: String,
+        #[structopt(long = "raw")]
+        /// Get value as stored, without schema defaults.
+        raw: bool,
+        #[structopt(long = "flat", conflicts_with = "raw")]
+        /// Get each leaf value on a line, as `path = value`.
+        flat: bool,
+    },
 
     #[structopt(name = "set")]
     /// Set value in Nest.
     Set { path: String, value: Option<String> },
+
+    #[structopt(name = "diff")]
+    /// Show changes from this Nest to another Nest with the same schema.
+    Diff {
+        #[structopt(parse(from_os_str))]
+        other: PathBuf,
+    },
+
+    #[structopt(name = "rekey")]
+    /// Re-encrypt encrypted sources with a new key file, to then replace the old key file.
+    Rekey {
+        #[structopt(parse(from_os_str))]
+        key_file: PathBuf,
+    },
+
+    #[structopt(name = "export")]
+    /// Export Nest as a (path, value) table in a SQLite database.
+    Export {
+        #[structopt(long = "sqlite", parse(from_os_str))]
+        /// SQLite database file to write.
+        sqlite: PathBuf,
+    },
+
+    #[structopt(name = "import")]
+    /// Import Nest from a (path, value) table in a SQLite database.
+    Import {
+        #[structopt(long = "sqlite", parse(from_os_str))]
+        /// SQLite database file to read.
+        sqlite: PathBuf,
+    },
+
+    #[structopt(name = "init")]
+    /// Infer schema from existing files and write .nest.json.
+    Init {
+        #[structopt(long = "force")]
+        /// Overwrite an existing .nest.json.
+        force: bool,
+    },
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -60,23 +104,45 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     debug!("root: {:#?}", root);
     let schema_path = root.join(".nest.json");
     debug!("schema path: {:#?}", schema_path);
+
+    if let Command::Init { force } = args.command {
+        if schema_path.exists() && !force {
+            error!("schema path already exists: {:#?}", schema_path);
+            return Ok(());
+        }
+        let schema = Schema::infer(&root)?;
+        debug!("schema: {:#?}", schema);
+        let schema_value: Value = (&schema).into();
+        let schema_json: json::Value = schema_value.try_into()?;
+        let mut schema_string = json::to_string_pretty(&schema_json)?;
+        schema_string.push('\n');
+        write(schema_path, schema_string)?;
+        return Ok(());
+    }
+
     if !schema_path.is_file() {
         error!("schema path is not a file: {:#?}", schema_path);
         return Ok(());
     }
-    let schema_str = read_to_string(schema_path)?;
-    debug!("schema string: {:#?}", schema_str);
-    let schema_json: json::Value = json::from_str(&schema_str)?;
-    debug!("schema json: {:#?}", schema_json);
-    let schema: Schema = schema_json.try_into()?;
+    let schema = Schema::from_file(&schema_path)?;
     debug!("schema: {:#?}", schema);
 
-    let store = Store::new(root, schema);
+    let store = Store::new(root, schema.clone());
 
     match args.command {
-        Command::Get { path } => {
+        Command::Get { path, raw, flat } => {
             let path = parse_path(&path);
-            let value = store.get(&path)?;
+            if flat {
+                for (leaf_path, value) in store.flatten(&path)? {
+                    println!("{} = {}", leaf_path, format_value(value)?);
+                }
+                return Ok(());
+            }
+            let value = if raw {
+                store.get_raw(&path)?
+            } else {
+                store.get(&path)?
+            };
             let value_json: json::Value = value.try_into()?;
             let value_string = json::to_string_pretty(&value_json)?;
             println!("{}", value_string);
@@ -94,18 +160,86 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 }
             };
             let value_json: json::Value = json::from_str(&value_str)?;
-            let value: Value = value_json.try_into()?;
+            let value: Value = value_json.into();
             store.set(&path, &value)?;
         }
+        Command::Diff { other } => {
+            let other_store = Store::new(other, schema).with_missing(Missing::Skip);
+            let store = store.with_missing(Missing::Skip);
+            for change in store.diff(&other_store)? {
+                match change {
+                    Change::Add { path, value } => {
+                        println!("+ {} = {}", path, format_value(value)?);
+                    }
+                    Change::Remove { path, value } => {
+                        println!("- {} = {}", path, format_value(value)?);
+                    }
+                    Change::Modify { path, from, to } => {
+                        println!(
+                            "~ {} = {} -> {}",
+                            path,
+                            format_value(from)?,
+                            format_value(to)?
+                        );
+                    }
+                }
+            }
+        }
+        Command::Rekey { key_file } => {
+            // any pinned key files are replaced in .nest.json, which is rewritten without includes
+            let schema_json: json::Value = json::from_str(&read_to_string(&schema_path)?)?;
+            let schema_value: Value = (&schema).into();
+            let schema_value_json: json::Value = schema_value.try_into()?;
+            let is_pinned = contains_key(&schema_value_json, "$key");
+            if is_pinned && contains_key(&schema_json, "$include") {
+                error!(
+                    "schema pins key files and has includes, update \"$key\" by hand: {:#?}",
+                    schema_path
+                );
+                return Ok(());
+            }
+
+            let next_schema = store.rekey(key_file)?;
+            if is_pinned {
+                let next_schema_value: Value = (&next_schema).into();
+                let next_schema_json: json::Value = next_schema_value.try_into()?;
+                let mut schema_string = json::to_string_pretty(&next_schema_json)?;
+                schema_string.push('\n');
+                write(schema_path, schema_string)?;
+            }
+        }
+        Command::Export { sqlite } => {
+            store.export_sqlite(sqlite)?;
+        }
+        Command::Import { sqlite } => {
+            store.import_sqlite(sqlite)?;
+        }
+        Command::Init { .. } => unreachable!(),
     }
 
     Ok(())
 }
 
 fn parse_path(path: &str) -> Vec<&str> {
-    if path != "" {
+    if !path.is_empty() {
         path.split('/').collect()
     } else {
         Vec::new()
     }
 }
+
+// whether `key` is in any object within `value`
+fn contains_key(value: &json::Value, key: &str) -> bool {
+    match value {
+        json::Value::Object(map) => {
+            map.contains_key(key) || map.values().any(|value| contains_key(value, key))
+        }
+        json::Value::Array(array) => array.iter().any(|value| contains_key(value, key)),
+        _ => false,
+    }
+}
+
+fn format_value(value: Value) -> Result<String, Box<dyn std::error::Error>> {
+    let value_json: json::Value = value.try_into()?;
+    Ok(json::to_string(&value_json)?)
+}
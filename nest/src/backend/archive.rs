@@ -0,0 +1,257 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Cursor, Read, Seek, Write};
+use std::path::{Component, Path, PathBuf};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use zip::write::FileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+use super::{Backend, InMemory, Metadata};
+
+/// The kinds of archive file an `Archive` can be read from, or a `Store` exported as.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArchiveFormat {
+    /// A tarball, `.tar`.
+    Tar,
+    /// A gzipped tarball, `.tar.gz` or `.tgz`.
+    TarGz,
+    /// A zip file, `.zip`.
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// The format of the archive file at `path`, from its extension.
+    pub fn from_path<P>(path: P) -> Option<ArchiveFormat>
+    where
+        P: AsRef<Path>,
+    {
+        let file_name = path.as_ref().file_name()?.to_string_lossy().to_lowercase();
+        if file_name.ends_with(".tar") {
+            Some(ArchiveFormat::Tar)
+        } else if file_name.ends_with(".tar.gz") || file_name.ends_with(".tgz") {
+            Some(ArchiveFormat::TarGz)
+        } else if file_name.ends_with(".zip") {
+            Some(ArchiveFormat::Zip)
+        } else {
+            None
+        }
+    }
+}
+
+/// A read-only `Backend` serving the files in a `.tar`, `.tar.gz` or `.zip` archive.
+///
+/// The archive is read into memory when opened. Paths are relative to the top of the archive,
+/// so use an empty `Store` root:
+///
+/// ```no_run
+/// use nest::backend::Archive;
+/// use nest::{Schema, Store};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let schema = Schema::from_file("bundle.nest.json")?;
+/// let store = Store::new("", schema).with_backend(Archive::open("bundle.tar.gz")?);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Every change fails with an `io::ErrorKind::PermissionDenied` error.
+#[derive(Clone, Debug)]
+pub struct Archive {
+    files: InMemory,
+}
+
+impl Archive {
+    /// Open the archive file at `path`, with the format given by its extension.
+    pub fn open<P>(path: P) -> io::Result<Archive>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let format = ArchiveFormat::from_path(path).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Unknown archive format: {}", path.display()),
+            )
+        })?;
+        Archive::from_reader(format, File::open(path)?)
+    }
+
+    /// Read an archive of the given `format` from `reader`.
+    pub fn from_reader<R>(format: ArchiveFormat, reader: R) -> io::Result<Archive>
+    where
+        R: Read,
+    {
+        let files = match format {
+            ArchiveFormat::Tar => read_tar(reader)?,
+            ArchiveFormat::TarGz => read_tar(GzDecoder::new(reader))?,
+            ArchiveFormat::Zip => {
+                // zip files are read from the end, so need to seek
+                let mut bytes = Vec::new();
+                let mut reader = reader;
+                reader.read_to_end(&mut bytes)?;
+                read_zip(Cursor::new(bytes))?
+            }
+        };
+
+        let archive = Archive {
+            files: InMemory::new(),
+        };
+        files.into_iter().try_for_each(|(file_path, bytes)| {
+            if let Some(directory_path) = file_path.parent() {
+                archive.files.create_dir(directory_path)?;
+            }
+            archive.files.write(&file_path, &bytes)
+        })?;
+        Ok(archive)
+    }
+}
+
+fn read_only(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::PermissionDenied,
+        format!("Read-only archive: {}", path.display()),
+    )
+}
+
+// archive entry paths, without any leading `./`, must stay within the archive
+fn entry_path(path: &Path) -> io::Result<PathBuf> {
+    let entry_path: PathBuf = path
+        .components()
+        .filter(|component| *component != Component::CurDir)
+        .collect();
+    if path.is_absolute()
+        || entry_path
+            .components()
+            .any(|component| component == Component::ParentDir)
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Invalid archive entry: {}", path.display()),
+        ));
+    }
+    Ok(entry_path)
+}
+
+fn read_tar<R>(reader: R) -> io::Result<BTreeMap<PathBuf, Vec<u8>>>
+where
+    R: Read,
+{
+    let mut archive = tar::Archive::new(reader);
+    let mut files = BTreeMap::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let file_path = entry_path(&entry.path()?)?;
+        let mut bytes = Vec::new();
+        entry.read_to_end(&mut bytes)?;
+        files.insert(file_path, bytes);
+    }
+    Ok(files)
+}
+
+fn read_zip<R>(reader: R) -> io::Result<BTreeMap<PathBuf, Vec<u8>>>
+where
+    R: Read + Seek,
+{
+    let mut archive = ZipArchive::new(reader)?;
+    let mut files = BTreeMap::new();
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index)?;
+        if file.is_dir() {
+            continue;
+        }
+        let file_path = entry_path(Path::new(file.name()))?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+        files.insert(file_path, bytes);
+    }
+    Ok(files)
+}
+
+/// Write `files`, by path within the archive, as an archive of the given `format`.
+pub(crate) fn write_archive(
+    format: ArchiveFormat,
+    files: &BTreeMap<PathBuf, Vec<u8>>,
+) -> io::Result<Vec<u8>> {
+    match format {
+        ArchiveFormat::Tar => write_tar(Vec::new(), files),
+        ArchiveFormat::TarGz => {
+            write_tar(GzEncoder::new(Vec::new(), Compression::default()), files)?.finish()
+        }
+        ArchiveFormat::Zip => {
+            let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+            files.iter().try_for_each(|(file_path, bytes)| {
+                writer.start_file(zip_name(file_path), FileOptions::default())?;
+                writer.write_all(bytes)
+            })?;
+            Ok(writer.finish()?.into_inner())
+        }
+    }
+}
+
+fn write_tar<W>(writer: W, files: &BTreeMap<PathBuf, Vec<u8>>) -> io::Result<W>
+where
+    W: Write,
+{
+    let mut builder = tar::Builder::new(writer);
+    files.iter().try_for_each(|(file_path, bytes)| {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(bytes.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, file_path, bytes.as_slice())
+    })?;
+    builder.into_inner()
+}
+
+// zip entry names always use `/`
+fn zip_name(file_path: &Path) -> String {
+    file_path
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+impl Backend for Archive {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.files.read(path)
+    }
+
+    fn write(&self, path: &Path, _bytes: &[u8]) -> io::Result<()> {
+        Err(read_only(path))
+    }
+
+    fn list(&self, path: &Path) -> io::Result<Vec<String>> {
+        self.files.list(path)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        Err(read_only(path))
+    }
+
+    fn remove_dir(&self, path: &Path) -> io::Result<()> {
+        Err(read_only(path))
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        Err(read_only(path))
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        self.files.metadata(path)
+    }
+
+    fn rename(&self, from: &Path, _to: &Path) -> io::Result<()> {
+        Err(read_only(from))
+    }
+
+    fn append(&self, path: &Path, _bytes: &[u8]) -> io::Result<()> {
+        Err(read_only(path))
+    }
+}
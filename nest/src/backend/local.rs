@@ -0,0 +1,71 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+use atomicwrites::{AtomicFile, OverwriteBehavior};
+use mkdirp::mkdirp;
+
+use super::{Backend, Metadata};
+
+/// The local filesystem, the default `Backend`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LocalFs;
+
+impl Backend for LocalFs {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(path)
+    }
+
+    fn write(&self, path: &Path, bytes: &[u8]) -> io::Result<()> {
+        let atomic_file = AtomicFile::new(path, OverwriteBehavior::AllowOverwrite);
+        match atomic_file.write(|file| file.write_all(bytes)) {
+            Ok(()) => Ok(()),
+            Err(atomicwrites::Error::Internal(io_error)) => Err(io_error),
+            Err(atomicwrites::Error::User(io_error)) => Err(io_error),
+        }
+    }
+
+    fn list(&self, path: &Path) -> io::Result<Vec<String>> {
+        fs::read_dir(path)?
+            .map(|entry| {
+                let entry = entry?;
+                Ok(entry.file_name().to_string_lossy().into_owned())
+            })
+            .collect()
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+
+    fn remove_dir(&self, path: &Path) -> io::Result<()> {
+        fs::remove_dir_all(path)
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        mkdirp(&path).map(|_| ())
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        let metadata = fs::metadata(path)?;
+        if metadata.is_dir() {
+            Ok(Metadata::Directory)
+        } else {
+            Ok(Metadata::File {
+                len: metadata.len(),
+            })
+        }
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        fs::rename(from, to)
+    }
+
+    fn append(&self, path: &Path, bytes: &[u8]) -> io::Result<()> {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| file.write_all(bytes))
+    }
+}
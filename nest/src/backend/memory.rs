@@ -0,0 +1,166 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use super::{Backend, Metadata};
+
+#[derive(Clone, Debug, PartialEq)]
+enum Entry {
+    File(Vec<u8>),
+    Directory,
+}
+
+/// A `Backend` held in memory, such as for tests.
+///
+/// Clones share the same files and directories, so a clone can inspect what a `Store` wrote.
+#[derive(Clone, Debug, Default)]
+pub struct InMemory {
+    entries: Arc<Mutex<BTreeMap<PathBuf, Entry>>>,
+}
+
+impl InMemory {
+    pub fn new() -> Self {
+        InMemory::default()
+    }
+
+    /// The bytes of every file, by path.
+    pub fn files(&self) -> BTreeMap<PathBuf, Vec<u8>> {
+        self.entries()
+            .iter()
+            .filter_map(|(path, entry)| match entry {
+                Entry::File(bytes) => Some((path.clone(), bytes.clone())),
+                Entry::Directory => None,
+            })
+            .collect()
+    }
+
+    fn entries(&self) -> MutexGuard<'_, BTreeMap<PathBuf, Entry>> {
+        // a panic while holding the lock can not leave the entries inconsistent
+        match self.entries.lock() {
+            Ok(entries) => entries,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+// the root (`/` or an empty relative path) always exists
+fn is_root(path: &Path) -> bool {
+    path.parent().is_none() || path.as_os_str().is_empty()
+}
+
+fn not_found(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("No such file or directory: {}", path.display()),
+    )
+}
+
+fn is_a_directory(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("Is a directory: {}", path.display()),
+    )
+}
+
+fn is_directory(entries: &BTreeMap<PathBuf, Entry>, path: &Path) -> bool {
+    is_root(path) || entries.get(path) == Some(&Entry::Directory)
+}
+
+impl Backend for InMemory {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        match self.entries().get(path) {
+            Some(Entry::File(bytes)) => Ok(bytes.clone()),
+            Some(Entry::Directory) => Err(is_a_directory(path)),
+            None => Err(not_found(path)),
+        }
+    }
+
+    fn write(&self, path: &Path, bytes: &[u8]) -> io::Result<()> {
+        let mut entries = self.entries();
+        let parent = path.parent().unwrap_or_else(|| Path::new(""));
+        if !is_directory(&entries, parent) {
+            return Err(not_found(parent));
+        }
+        if entries.get(path) == Some(&Entry::Directory) {
+            return Err(is_a_directory(path));
+        }
+        entries.insert(path.to_path_buf(), Entry::File(bytes.to_vec()));
+        Ok(())
+    }
+
+    fn list(&self, path: &Path) -> io::Result<Vec<String>> {
+        let entries = self.entries();
+        if !is_directory(&entries, path) {
+            return Err(not_found(path));
+        }
+        Ok(entries
+            .keys()
+            .filter(|entry_path| entry_path.parent() == Some(path))
+            .filter_map(|entry_path| entry_path.file_name())
+            .map(|file_name| file_name.to_string_lossy().into_owned())
+            .collect())
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        let mut entries = self.entries();
+        match entries.get(path) {
+            Some(Entry::File(_)) => {
+                entries.remove(path);
+                Ok(())
+            }
+            Some(Entry::Directory) => Err(is_a_directory(path)),
+            None => Err(not_found(path)),
+        }
+    }
+
+    fn remove_dir(&self, path: &Path) -> io::Result<()> {
+        let mut entries = self.entries();
+        if entries.get(path) != Some(&Entry::Directory) {
+            return Err(not_found(path));
+        }
+        let removed_paths: Vec<PathBuf> = entries
+            .keys()
+            .filter(|entry_path| entry_path.starts_with(path))
+            .cloned()
+            .collect();
+        removed_paths.iter().for_each(|removed_path| {
+            entries.remove(removed_path);
+        });
+        Ok(())
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        let mut entries = self.entries();
+        let mut directory_paths: Vec<&Path> = path
+            .ancestors()
+            .take_while(|directory_path| !is_root(directory_path))
+            .collect();
+        directory_paths.reverse();
+        directory_paths.into_iter().try_for_each(|directory_path| {
+            match entries.get(directory_path) {
+                Some(Entry::Directory) => Ok(()),
+                Some(Entry::File(_)) => Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("Not a directory: {}", directory_path.display()),
+                )),
+                None => {
+                    entries.insert(directory_path.to_path_buf(), Entry::Directory);
+                    Ok(())
+                }
+            }
+        })
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        let entries = self.entries();
+        match entries.get(path) {
+            Some(Entry::File(bytes)) => Ok(Metadata::File {
+                len: bytes.len() as u64,
+            }),
+            Some(Entry::Directory) => Ok(Metadata::Directory),
+            None if is_root(path) => Ok(Metadata::Directory),
+            None => Err(not_found(path)),
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+#[cfg(feature = "archive")]
+mod archive;
+mod local;
+mod memory;
+
+#[cfg(feature = "archive")]
+pub(crate) use self::archive::write_archive;
+#[cfg(feature = "archive")]
+pub use self::archive::{Archive, ArchiveFormat};
+pub use self::local::LocalFs;
+pub use self::memory::InMemory;
+
+/// Where a `Store` keeps its sources (files) and directories, added with
+/// [`Store::with_backend`](../struct.Store.html#method.with_backend).
+///
+/// Every method fails with an `io::ErrorKind::NotFound` error if nothing exists at the path,
+/// like `std::fs`.
+pub trait Backend: Send + Sync + fmt::Debug {
+    /// Read the bytes of the file at `path`.
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+    /// Write `bytes` as the file at `path` atomically, so it is never partially written. The
+    /// parent directory must exist.
+    fn write(&self, path: &Path, bytes: &[u8]) -> io::Result<()>;
+    /// The file names of every entry in the directory at `path`, in any order.
+    fn list(&self, path: &Path) -> io::Result<Vec<String>>;
+    /// Remove the file at `path`.
+    fn remove_file(&self, path: &Path) -> io::Result<()>;
+    /// Remove the directory at `path`, with everything in it.
+    fn remove_dir(&self, path: &Path) -> io::Result<()>;
+    /// Create the directory at `path`, with any missing parent directories.
+    fn create_dir(&self, path: &Path) -> io::Result<()>;
+    /// What exists at `path`.
+    fn metadata(&self, path: &Path) -> io::Result<Metadata>;
+
+    /// Move the file at `from` to `to`, replacing any file there.
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let bytes = self.read(from)?;
+        self.write(to, &bytes)?;
+        self.remove_file(from)
+    }
+
+    /// Append `bytes` to the file at `path`, creating it if needed.
+    fn append(&self, path: &Path, bytes: &[u8]) -> io::Result<()> {
+        let mut file_bytes = match self.read(path) {
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => Vec::new(),
+            result => result?,
+        };
+        file_bytes.extend_from_slice(bytes);
+        self.write(path, &file_bytes)
+    }
+
+    /// Whether a file exists at `path`.
+    fn is_file(&self, path: &Path) -> bool {
+        self.metadata(path)
+            .map(|metadata| metadata.is_file())
+            .unwrap_or(false)
+    }
+
+    /// Whether a directory exists at `path`.
+    fn is_dir(&self, path: &Path) -> bool {
+        self.metadata(path)
+            .map(|metadata| metadata.is_dir())
+            .unwrap_or(false)
+    }
+}
+
+/// What exists at a path in a `Backend`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Metadata {
+    /// A file, with its length in bytes.
+    File { len: u64 },
+    /// A directory.
+    Directory,
+}
+
+impl Metadata {
+    pub fn is_file(&self) -> bool {
+        match self {
+            Metadata::File { .. } => true,
+            Metadata::Directory => false,
+        }
+    }
+
+    pub fn is_dir(&self) -> bool {
+        !self.is_file()
+    }
+}
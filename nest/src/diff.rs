@@ -0,0 +1,63 @@
+use crate::path::Path;
+use crate::value::Value;
+
+/// A change at a path between two values, as returned by [`diff`](fn.diff.html).
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change {
+    Add { path: Path, value: Value },
+    Remove { path: Path, value: Value },
+    Modify { path: Path, from: Value, to: Value },
+}
+
+impl Change {
+    pub fn path(&self) -> &Path {
+        match self {
+            Change::Add { path, .. }
+            | Change::Remove { path, .. }
+            | Change::Modify { path, .. } => path,
+        }
+    }
+}
+
+/// Compare two values, returning the path-level changes from `from` to `to`.
+///
+/// Objects are compared key-wise, anything else (including arrays) is compared as a whole.
+pub fn diff(from: &Value, to: &Value) -> Vec<Change> {
+    let mut changes = Vec::new();
+    diff_at(Path::default(), from, to, &mut changes);
+    changes
+}
+
+fn diff_at(path: Path, from: &Value, to: &Value, changes: &mut Vec<Change>) {
+    match (from, to) {
+        (Value::Object(from_map), Value::Object(to_map)) => {
+            from_map
+                .iter()
+                .for_each(|(key, from_value)| match to_map.get(key) {
+                    Some(to_value) => diff_at(path.append(key), from_value, to_value, changes),
+                    None => changes.push(Change::Remove {
+                        path: path.append(key),
+                        value: from_value.clone(),
+                    }),
+                });
+            to_map
+                .iter()
+                .filter(|(key, _)| !from_map.contains_key(*key))
+                .for_each(|(key, to_value)| {
+                    changes.push(Change::Add {
+                        path: path.append(key),
+                        value: to_value.clone(),
+                    })
+                });
+        }
+        _ => {
+            if from != to {
+                changes.push(Change::Modify {
+                    path,
+                    from: from.clone(),
+                    to: to.clone(),
+                });
+            }
+        }
+    }
+}
@@ -56,21 +56,133 @@ pub enum Error {
         path: path::PathBuf,
         source: io::Error,
     },
+    #[snafu(display("Could not remove file at {}: {}", path.display(), source))]
+    RemoveSource {
+        path: path::PathBuf,
+        source: io::Error,
+    },
+    #[snafu(display("Could not remove directory at {}: {}", path.display(), source))]
+    RemoveDirectory {
+        path: path::PathBuf,
+        source: io::Error,
+    },
+    #[snafu(display("Could not read directory at {}: {}", path.display(), source))]
+    ReadDirectory {
+        path: path::PathBuf,
+        source: io::Error,
+    },
     #[snafu(display("Could not make directory at {}: {}", path.display(), source))]
     MakeDirectory {
         path: path::PathBuf,
         source: io::Error,
     },
+    #[snafu(display("No file name for source at {:?}", path))]
+    SourceFileName { path: path::PathBuf },
+    #[cfg(feature = "archive")]
+    #[snafu(display("Could not read snapshot: {}", source))]
+    ReadSnapshot { source: io::Error },
+    #[cfg(feature = "archive")]
+    #[snafu(display("Could not write snapshot: {}", source))]
+    WriteSnapshot { source: io::Error },
+    #[cfg(feature = "archive")]
+    #[snafu(display("Invalid snapshot entry at {}", path.display()))]
+    InvalidSnapshot { path: path::PathBuf },
+    #[cfg(feature = "archive")]
+    #[snafu(display("Unknown archive format for {}", path.display()))]
+    UnknownArchiveFormat { path: path::PathBuf },
+    #[cfg(feature = "archive")]
+    #[snafu(display("Could not write archive at {}: {}", path.display(), source))]
+    WriteArchive {
+        path: path::PathBuf,
+        source: io::Error,
+    },
+    #[cfg(feature = "encryption")]
+    #[snafu(display("Could not read key file at {}: {}", path.display(), source))]
+    KeyFile {
+        path: path::PathBuf,
+        source: io::Error,
+    },
+    #[cfg(feature = "encryption")]
+    #[snafu(display("No key file in schema or NEST_KEY_FILE environment variable"))]
+    MissingKeyFile,
+    #[cfg(feature = "encryption")]
+    #[snafu(display("Could not encrypt value at {}", path.display()))]
+    Encrypt { path: path::PathBuf },
+    #[cfg(feature = "encryption")]
+    #[snafu(display("Could not decrypt file at {} (wrong key or corrupt file)", path.display()))]
+    Decrypt { path: path::PathBuf },
+    #[snafu(display("Middleware failed at {}: {}", path, source))]
+    Middleware { path: Path, source: BoxError },
+    #[snafu(display("Could not read journal: {}", source))]
+    ReadJournal { source: io::Error },
+    #[snafu(display("Could not write journal at {}: {}", path.display(), source))]
+    WriteJournal {
+        path: path::PathBuf,
+        source: io::Error,
+    },
+    #[snafu(display("Invalid journal entry on line {}", line))]
+    InvalidJournal { line: usize },
+    #[cfg(feature = "git")]
+    #[snafu(display("Git error: {}", source))]
+    Git { source: BoxError },
+    #[cfg(feature = "git")]
+    #[snafu(display("Not within a git repository working directory: {}", path.display()))]
+    GitPath { path: path::PathBuf },
+    #[cfg(feature = "sqlite")]
+    #[snafu(display("SQLite error at {}: {}", path.display(), source))]
+    Sqlite {
+        path: path::PathBuf,
+        source: BoxError,
+    },
+    #[snafu(display("Could not read schema at {}: {}", path.display(), source))]
+    ReadSchema {
+        path: path::PathBuf,
+        source: io::Error,
+    },
+    #[snafu(display("Schema at {} includes itself", path.display()))]
+    IncludeCycle { path: path::PathBuf },
+    #[snafu(display("Snapshot at {} includes a mounted store", path))]
+    SnapshotMount { path: Path },
+    #[snafu(display("Link at {} leads back to itself", path))]
+    LinkCycle { path: Path },
+    #[snafu(display("Link at {} leads outside of the sub-store at {}", path, sub_path))]
+    SubLink { path: Path, sub_path: Path },
     #[snafu(display("Schema not found at {}", path))]
     GetSchema { path: Path },
     #[snafu(display("Value not found at {}", path))]
     GetValue { path: Path },
+    #[snafu(display("Could not resolve {} in value at {}", reference, path))]
+    UnresolvedReference { reference: String, path: Path },
+    #[snafu(display("Reference cycle through {} in value at {}", reference, path))]
+    ReferenceCycle { reference: String, path: Path },
+    #[snafu(display("Layer not found at index {}", layer))]
+    GetLayer { layer: usize },
     #[snafu(display("Expected object value for directory schema at {}", path))]
     SetObjectValueWhenDirectory { path: Path },
+    #[snafu(display("Unknown key not in directory schema at {}", path))]
+    SetUnknownKey { path: Path },
     #[snafu(display("Invalid schema from value: {:#?}", value))]
     InvalidSchema { value: Value },
+    #[snafu(display("Invalid patch operation from value: {:#?}", value))]
+    InvalidPatch { value: Box<Value> },
+    #[snafu(display("Invalid or missing JSON pointer in patch: {}", pointer))]
+    PatchPointer { pointer: String },
+    #[snafu(display("Patch test failed at {}", pointer))]
+    PatchTest { pointer: String },
     #[snafu(display("Unexpected (programmer) error"))]
     Unexpected,
 }
 
+impl Error {
+    // whether a source (file) or directory does not exist
+    pub(crate) fn is_not_found(&self) -> bool {
+        match self {
+            Error::ReadSource { source, .. } | Error::ReadDirectory { source, .. } => {
+                source.kind() == io::ErrorKind::NotFound
+            }
+            _ => false,
+        }
+    }
+}
+
 pub type Result<A, B = Error> = std::result::Result<A, B>;
@@ -0,0 +1,49 @@
+use indexmap::IndexMap;
+
+use crate::path::Path;
+use crate::value::Value;
+
+// every leaf within `value` (at `path`) with its full path, in order: anything but a non-empty
+// object is a leaf, since arrays can't be addressed by path
+pub(crate) fn flatten(path: Path, value: Value) -> Vec<(Path, Value)> {
+    let mut pairs = Vec::new();
+    flatten_into(path, value, &mut pairs);
+    pairs
+}
+
+fn flatten_into(path: Path, value: Value, pairs: &mut Vec<(Path, Value)>) {
+    match value {
+        Value::Object(map) if !map.is_empty() => map.into_iter().for_each(|(key, value)| {
+            flatten_into(path.append(&key), value, pairs);
+        }),
+        value => pairs.push((path, value)),
+    }
+}
+
+// the value with every leaf at its path, where later leaves replace earlier ones
+pub(crate) fn unflatten<I>(pairs: I) -> Value
+where
+    I: IntoIterator<Item = (Path, Value)>,
+{
+    let mut value = Value::Object(IndexMap::new());
+    pairs
+        .into_iter()
+        .for_each(|(path, leaf)| insert(&mut value, &path, leaf));
+    value
+}
+
+fn insert(value: &mut Value, path: &Path, leaf: Value) {
+    if path.is_empty() {
+        *value = leaf;
+        return;
+    }
+    if !value.is_object() {
+        *value = Value::Object(IndexMap::new());
+    }
+    if let Value::Object(map) = value {
+        let nested_value = map
+            .entry(path.first().clone())
+            .or_insert_with(|| Value::Object(IndexMap::new()));
+        insert(nested_value, &path.rest(), leaf);
+    }
+}
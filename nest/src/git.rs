@@ -0,0 +1,202 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path;
+
+use git2::{Commit, ErrorCode, ObjectType, Repository, Signature, TreeWalkMode, TreeWalkResult};
+use snafu::{OptionExt, ResultExt};
+
+use crate::error::{self, BoxError, Result};
+use crate::value::Value;
+
+/// A past `Value` at a path, from a commit in the git repository of a `Store`, as returned by
+/// [`Store::history`](struct.Store.html#method.history).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Revision {
+    /// The commit id, as hex.
+    pub id: String,
+    /// The commit time, in seconds since the Unix epoch.
+    pub time: i64,
+    /// The commit message.
+    pub message: String,
+    /// The `Value` at the path as of the commit.
+    pub value: Value,
+}
+
+// a commit, with the sources (files) under an origin by file path relative to the store root
+pub(crate) struct CommitFiles {
+    pub id: String,
+    pub time: i64,
+    pub message: String,
+    pub files: BTreeMap<path::PathBuf, Vec<u8>>,
+}
+
+fn git_error(err: git2::Error) -> BoxError {
+    Box::new(err)
+}
+
+// the repository containing `root`, and `root` relative to the repository working directory
+fn open(root: &path::Path) -> Result<(Repository, path::PathBuf)> {
+    let repo = Repository::discover(root)
+        .map_err(git_error)
+        .context(error::Git)?;
+    let workdir = repo.workdir().context(error::GitPath { path: root })?;
+    let workdir = fs::canonicalize(workdir).context(error::ReadDirectory { path: workdir })?;
+    let root_path = fs::canonicalize(root)
+        .context(error::ReadDirectory { path: root })?
+        .strip_prefix(&workdir)
+        .map(path::Path::to_path_buf)
+        .ok()
+        .context(error::GitPath { path: root })?;
+    Ok((repo, root_path))
+}
+
+// `origin` (within `root`) relative to the repository working directory
+fn repo_path(
+    root: &path::Path,
+    root_path: &path::Path,
+    origin: &path::Path,
+) -> Result<path::PathBuf> {
+    origin
+        .strip_prefix(root)
+        .map(|relative_path| root_path.join(relative_path))
+        .ok()
+        .context(error::GitPath { path: origin })
+}
+
+// stage the sources (files) at `origins`, which were written or removed, and commit them, unless
+// nothing changed
+pub(crate) fn commit(root: &path::Path, origins: &[path::PathBuf], message: &str) -> Result<()> {
+    let (repo, root_path) = open(root)?;
+    let mut index = repo.index().map_err(git_error).context(error::Git)?;
+    origins.iter().try_for_each(|origin| -> Result<()> {
+        let pathspec = repo_path(root, &root_path, origin)?;
+        if origin.is_file() {
+            index.add_path(&pathspec)
+        } else {
+            // a removed source (file) may never have been staged
+            match index.remove_path(&pathspec) {
+                Err(ref err) if err.code() == ErrorCode::NotFound => Ok(()),
+                result => result,
+            }
+        }
+        .map_err(git_error)
+        .context(error::Git)
+    })?;
+    index.write().map_err(git_error).context(error::Git)?;
+    let tree_id = index.write_tree().map_err(git_error).context(error::Git)?;
+
+    let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+    if let Some(ref parent) = parent {
+        if parent.tree_id() == tree_id {
+            return Ok(());
+        }
+    }
+
+    let tree = repo
+        .find_tree(tree_id)
+        .map_err(git_error)
+        .context(error::Git)?;
+    let signature = repo
+        .signature()
+        .or_else(|_| Signature::now("nest", "nest@localhost"))
+        .map_err(git_error)
+        .context(error::Git)?;
+    let parents: Vec<&Commit> = parent.iter().collect();
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        message,
+        &tree,
+        &parents,
+    )
+    .map_err(git_error)
+    .context(error::Git)?;
+
+    Ok(())
+}
+
+// every commit reachable from `HEAD`, oldest first, with the sources (files) at `origin`
+pub(crate) fn history(root: &path::Path, origin: &path::Path) -> Result<Vec<CommitFiles>> {
+    let (repo, root_path) = open(root)?;
+    let origin_path = repo_path(root, &root_path, origin)?;
+
+    let mut revwalk = repo.revwalk().map_err(git_error).context(error::Git)?;
+    if revwalk.push_head().is_err() {
+        // no commits yet
+        return Ok(Vec::new());
+    }
+    let mut ids = revwalk
+        .collect::<std::result::Result<Vec<git2::Oid>, git2::Error>>()
+        .map_err(git_error)
+        .context(error::Git)?;
+    ids.reverse();
+
+    ids.into_iter()
+        .map(|id| {
+            let commit = repo
+                .find_commit(id)
+                .map_err(git_error)
+                .context(error::Git)?;
+            let files = commit_files(&repo, &commit, &root_path, &origin_path)
+                .map_err(git_error)
+                .context(error::Git)?;
+            Ok(CommitFiles {
+                id: id.to_string(),
+                time: commit.time().seconds(),
+                message: commit.message().unwrap_or_default().to_owned(),
+                files,
+            })
+        })
+        .collect()
+}
+
+fn commit_files(
+    repo: &Repository,
+    commit: &Commit,
+    root_path: &path::Path,
+    origin_path: &path::Path,
+) -> std::result::Result<BTreeMap<path::PathBuf, Vec<u8>>, git2::Error> {
+    let commit_tree = commit.tree()?;
+    let mut blobs = Vec::new();
+
+    if origin_path.as_os_str().is_empty() {
+        collect_blobs(&commit_tree, origin_path, &mut blobs)?;
+    } else {
+        // the origin may not exist in this commit
+        let entry = match commit_tree.get_path(origin_path) {
+            Ok(entry) => entry,
+            Err(_) => return Ok(BTreeMap::new()),
+        };
+        match entry.kind() {
+            Some(ObjectType::Blob) => blobs.push((origin_path.to_path_buf(), entry.id())),
+            Some(ObjectType::Tree) => {
+                let tree = repo.find_tree(entry.id())?;
+                collect_blobs(&tree, origin_path, &mut blobs)?;
+            }
+            _ => {}
+        }
+    }
+
+    let mut files = BTreeMap::new();
+    for (blob_path, id) in blobs {
+        if let Ok(relative_path) = blob_path.strip_prefix(root_path) {
+            let blob = repo.find_blob(id)?;
+            files.insert(relative_path.to_path_buf(), blob.content().to_vec());
+        }
+    }
+    Ok(files)
+}
+
+fn collect_blobs(
+    tree: &git2::Tree,
+    tree_path: &path::Path,
+    blobs: &mut Vec<(path::PathBuf, git2::Oid)>,
+) -> std::result::Result<(), git2::Error> {
+    tree.walk(TreeWalkMode::PreOrder, |directory, entry| {
+        if let (Some(ObjectType::Blob), Some(name)) = (entry.kind(), entry.name()) {
+            blobs.push((tree_path.join(directory).join(name), entry.id()));
+        }
+        TreeWalkResult::Ok
+    })
+}
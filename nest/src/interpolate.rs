@@ -0,0 +1,153 @@
+use std::convert::TryFrom;
+use std::fmt;
+
+use indexmap::IndexMap;
+use snafu::ResultExt;
+
+use crate::error::{self, Result};
+use crate::path::Path;
+use crate::value::Value;
+
+const OPEN: &str = "${";
+const CLOSE: &str = "}";
+// `$${` is a literal `${`
+const ESCAPED_OPEN: &str = "$${";
+
+// a reference within a string value
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Reference {
+    // `${env:DATABASE_URL}`, an environment variable
+    Env(String),
+    // `${ref:hello/world/nest}`, a path relative to the store root
+    Ref(Path),
+}
+
+impl Reference {
+    fn parse(string: &str) -> Option<Reference> {
+        let mut parts = string.splitn(2, ':');
+        match (parts.next()?, parts.next()?) {
+            ("env", name) if !name.is_empty() => Some(Reference::Env(name.to_owned())),
+            ("ref", path) => Some(Reference::Ref(Path::parse(path))),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Reference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Reference::Env(name) => write!(f, "${{env:{}}}", name),
+            Reference::Ref(path) => {
+                let keys: Vec<&str> = path.iter().map(String::as_str).collect();
+                write!(f, "${{ref:{}}}", keys.join("/"))
+            }
+        }
+    }
+}
+
+// a part of a string value, split around references
+#[derive(Debug, PartialEq)]
+enum Part {
+    Text(String),
+    Reference(Reference),
+}
+
+fn parse_parts(string: &str) -> Vec<Part> {
+    let mut parts = Vec::new();
+    let mut text = String::new();
+    let mut rest = string;
+
+    while let Some(index) = rest.find('$') {
+        text.push_str(&rest[..index]);
+        rest = &rest[index..];
+
+        if let Some(after) = rest.strip_prefix(ESCAPED_OPEN) {
+            text.push_str(OPEN);
+            rest = after;
+            continue;
+        }
+
+        let reference = rest.strip_prefix(OPEN).and_then(|inner| {
+            let end = inner.find(CLOSE)?;
+            let reference = Reference::parse(&inner[..end])?;
+            Some((reference, OPEN.len() + end + CLOSE.len()))
+        });
+        match reference {
+            Some((reference, length)) => {
+                if !text.is_empty() {
+                    parts.push(Part::Text(text.split_off(0)));
+                }
+                parts.push(Part::Reference(reference));
+                rest = &rest[length..];
+            }
+            // not a reference, so keep the `$` as is
+            None => {
+                text.push('$');
+                rest = &rest[1..];
+            }
+        }
+    }
+    text.push_str(rest);
+    if !text.is_empty() {
+        parts.push(Part::Text(text));
+    }
+    parts
+}
+
+// expand every reference within the string values of `value` (at `path`) with `resolve`
+//
+// a string which is only a reference is replaced with the resolved value, otherwise each resolved
+// value is spliced into the string (as json, unless a string)
+pub(crate) fn interpolate<F>(path: &Path, value: Value, resolve: &mut F) -> Result<Value>
+where
+    F: FnMut(&Path, &Reference) -> Result<Value>,
+{
+    match value {
+        Value::Object(map) => map
+            .into_iter()
+            .map(|(key, nested_value)| {
+                let nested_value = interpolate(&path.append(&key), nested_value, resolve)?;
+                Ok((key, nested_value))
+            })
+            .collect::<Result<IndexMap<String, Value>>>()
+            .map(Value::Object),
+        Value::Array(array) => array
+            .into_iter()
+            .map(|item| interpolate(path, item, resolve))
+            .collect::<Result<Vec<Value>>>()
+            .map(Value::Array),
+        Value::String(string) => {
+            let parts = parse_parts(&string);
+            if parts.len() == 1 {
+                if let Part::Reference(ref reference) = parts[0] {
+                    return resolve(path, reference);
+                }
+            }
+            parts
+                .into_iter()
+                .map(|part| match part {
+                    Part::Text(text) => Ok(text),
+                    Part::Reference(reference) => {
+                        resolve(path, &reference).and_then(|value| splice_string(path, value))
+                    }
+                })
+                .collect::<Result<String>>()
+                .map(Value::String)
+        }
+        value => Ok(value),
+    }
+}
+
+fn splice_string(path: &Path, value: Value) -> Result<String> {
+    match value {
+        Value::String(string) => Ok(string),
+        value => serde_json::Value::try_from(value.clone())
+            .map(|json_value| json_value.to_string())
+            .map_err(|err| -> Box<dyn std::error::Error> { Box::new(err) })
+            .context(error::FromValue {
+                kind: "json",
+                path: path.to_path(),
+                value,
+            }),
+    }
+}
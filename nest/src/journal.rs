@@ -0,0 +1,163 @@
+use std::convert::TryFrom;
+use std::io::{BufRead, BufReader, Read};
+use std::path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use indexmap::IndexMap;
+use snafu::{OptionExt, ResultExt};
+
+use crate::backend::Backend;
+use crate::error::{self, Result};
+use crate::path::Path;
+use crate::value::Value;
+
+// the journal file, relative to the store root
+pub(crate) const JOURNAL_PATH: &str = ".nest/journal";
+
+// a change to a store, as a line in the journal:
+//
+// { "time": 1571234567890, "action": "set", "path": ["hello", "world"], "old": ..., "new": ..., "actor": "dinosaur" }
+//
+// `old` is missing if there was no value before, `new` is missing if the value was deleted.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct JournalEntry {
+    // milliseconds since the Unix epoch
+    pub time: u64,
+    pub action: String,
+    pub path: Path,
+    pub old: Option<Value>,
+    pub new: Option<Value>,
+    pub actor: Option<String>,
+}
+
+impl JournalEntry {
+    pub fn new(
+        action: &str,
+        path: Path,
+        old: Option<Value>,
+        new: Option<Value>,
+        actor: Option<String>,
+    ) -> Self {
+        let duration = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        JournalEntry {
+            time: duration.as_secs() * 1000 + u64::from(duration.subsec_millis()),
+            action: action.to_owned(),
+            path,
+            old,
+            new,
+            actor,
+        }
+    }
+}
+
+impl From<&JournalEntry> for Value {
+    fn from(entry: &JournalEntry) -> Value {
+        let mut map = IndexMap::new();
+        map.insert("time".to_owned(), Value::Uint(entry.time));
+        map.insert("action".to_owned(), Value::String(entry.action.clone()));
+        map.insert(
+            "path".to_owned(),
+            Value::Array(entry.path.iter().cloned().map(Value::String).collect()),
+        );
+        if let Some(ref old) = entry.old {
+            map.insert("old".to_owned(), old.clone());
+        }
+        if let Some(ref new) = entry.new {
+            map.insert("new".to_owned(), new.clone());
+        }
+        if let Some(ref actor) = entry.actor {
+            map.insert("actor".to_owned(), Value::String(actor.clone()));
+        }
+        Value::Object(map)
+    }
+}
+
+// the inverse of `Value::from(&JournalEntry)`
+fn parse_entry(value: Value) -> Option<JournalEntry> {
+    let mut map = match value {
+        Value::Object(map) => map,
+        _ => return None,
+    };
+    let time = match map.get("time")? {
+        Value::Uint(time) => *time,
+        Value::Int(time) if *time >= 0 => *time as u64,
+        _ => return None,
+    };
+    let action = match map.get("action")? {
+        Value::String(action) => action.clone(),
+        _ => return None,
+    };
+    let path = match map.get("path")? {
+        Value::Array(keys) => keys
+            .iter()
+            .map(|key| match key {
+                Value::String(key) => Some(key.clone()),
+                _ => None,
+            })
+            .collect::<Option<Vec<String>>>()?,
+        _ => return None,
+    };
+    let actor = match map.get("actor") {
+        Some(Value::String(actor)) => Some(actor.clone()),
+        Some(_) => return None,
+        None => None,
+    };
+    Some(JournalEntry {
+        time,
+        action,
+        path: (&path).into(),
+        old: map.remove("old"),
+        new: map.remove("new"),
+        actor,
+    })
+}
+
+// append an entry as a line to the journal of the store at `root`
+pub(crate) fn append(backend: &dyn Backend, root: &path::Path, entry: &JournalEntry) -> Result<()> {
+    let journal_path = root.join(JOURNAL_PATH);
+    let value = Value::from(entry);
+    let json_value = serde_json::Value::try_from(value.clone())
+        .map_err(|err| -> Box<dyn std::error::Error> { Box::new(err) })
+        .context(error::FromValue {
+            kind: "json",
+            path: &journal_path,
+            value,
+        })?;
+
+    if let Some(directory_path) = journal_path.parent() {
+        backend
+            .create_dir(directory_path)
+            .context(error::MakeDirectory {
+                path: directory_path,
+            })?;
+    }
+    backend
+        .append(&journal_path, format!("{}\n", json_value).as_bytes())
+        .context(error::WriteJournal {
+            path: &journal_path,
+        })
+}
+
+// read every entry from a journal, in order
+pub(crate) fn read<R>(journal: R) -> Result<Vec<JournalEntry>>
+where
+    R: Read,
+{
+    BufReader::new(journal)
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| match line {
+            Ok(line) => !line.trim().is_empty(),
+            Err(_) => true,
+        })
+        .map(|(index, line)| {
+            let line = line.context(error::ReadJournal)?;
+            serde_json::from_str::<serde_json::Value>(&line)
+                .ok()
+                .and_then(|json_value| parse_entry(Value::from(json_value)))
+                .context(error::InvalidJournal { line: index + 1 })
+        })
+        .collect()
+}
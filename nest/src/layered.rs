@@ -0,0 +1,132 @@
+use std::path;
+
+use log::info;
+use snafu::OptionExt;
+
+use crate::error::{self, Result};
+use crate::path::Path;
+use crate::store::{Missing, Store};
+use crate::value::Value;
+
+/// A stack of `Store`s (layers), where later layers take precedence over earlier layers.
+///
+/// Each layer has its own root and schema, such as base config, per-environment overrides and
+/// per-host overrides in separate directories.
+///
+/// # Example
+///
+/// ```rust, no_run
+/// use std::convert::TryInto;
+/// use serde_json::json;
+/// use nest::{Error, LayeredStore, Store, Value};
+///
+/// let schema: nest::Schema = json!({ "hello": { "world": "json" } }).try_into()?;
+/// let base = Store::new("/etc/example/base", schema.clone());
+/// let host = Store::new("/etc/example/host", schema);
+/// let store = LayeredStore::new(vec![base, host]);
+///
+/// // values are deep merged from every layer, in order
+/// let value = store.get(&["hello", "world"])?;
+///
+/// // and set in the top layer (by default, the last layer)
+/// store.set(&["hello", "world", "nest"], &Value::String("🐥".into()))?;
+/// # Ok::<(), Error>(())
+/// ```
+pub struct LayeredStore {
+    layers: Vec<Store>,
+    top: usize,
+}
+
+/// Where a value in a [`LayeredStore`](struct.LayeredStore.html) came from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Origin {
+    /// The index of the layer.
+    pub layer: usize,
+    /// The source (file) or directory within the layer.
+    pub path: path::PathBuf,
+}
+
+impl LayeredStore {
+    /// Create a `LayeredStore` from `layers`, in order of increasing precedence.
+    pub fn new(layers: Vec<Store>) -> Self {
+        info!("nest::LayeredStore::new({:?} layers)", layers.len());
+
+        let top = layers.len().saturating_sub(1);
+        LayeredStore { layers, top }
+    }
+
+    /// Set values in the layer at index `top`, rather than the last layer.
+    pub fn with_top(mut self, top: usize) -> Self {
+        self.top = top;
+        self
+    }
+
+    /// Return the layers, in order of increasing precedence.
+    pub fn layers(&self) -> &[Store] {
+        &self.layers
+    }
+
+    /// Get the `Value` at the given `path`, deep merged from every layer that has it.
+    ///
+    /// A layer without a value stored at `path` is skipped, as are missing sources (files) within
+    /// a layer, so a layer may override only some of the sources. Any other error from a layer
+    /// (such as a source which fails to parse) is returned.
+    pub fn get<A>(&self, path: A) -> Result<Value>
+    where
+        A: Into<Path>,
+    {
+        let path = path.into();
+        info!("nest::LayeredStore#get({:?})", path);
+
+        let mut value: Option<Value> = None;
+        self.layers.iter().try_for_each(|layer| -> Result<()> {
+            if layer.has_value(path.clone())? {
+                let layer_value = layer.get_with_missing(path.clone(), Missing::Skip)?;
+                value = Some(match value.take() {
+                    Some(value) => value.deep_merge(layer_value),
+                    None => layer_value,
+                });
+            }
+            Ok(())
+        })?;
+
+        value.context(error::GetValue { path })
+    }
+
+    /// Set the `Value` at the given `path` in the top layer.
+    pub fn set<A>(&self, path: A, value: &Value) -> Result<()>
+    where
+        A: Into<Path>,
+    {
+        let path = path.into();
+        info!("nest::LayeredStore#set({:?}, {:?})", path, value);
+
+        let layer = self
+            .layers
+            .get(self.top)
+            .context(error::GetLayer { layer: self.top })?;
+        layer.set(path, value)
+    }
+
+    /// Get the highest precedence layer with a `Value` at the given `path`, and where it is stored.
+    pub fn get_origin<A>(&self, path: A) -> Result<Origin>
+    where
+        A: Into<Path>,
+    {
+        let path = path.into();
+        info!("nest::LayeredStore#get_origin({:?})", path);
+
+        let mut origin = None;
+        for (index, layer) in self.layers.iter().enumerate().rev() {
+            if layer.has_value(path.clone())? {
+                origin = Some(Origin {
+                    layer: index,
+                    path: layer.get_origin(path.clone())?,
+                });
+                break;
+            }
+        }
+
+        origin.context(error::GetValue { path })
+    }
+}
@@ -9,15 +9,37 @@
 #[macro_use]
 extern crate lazy_static;
 
+pub use self::backend::Backend;
+pub use self::diff::{diff, Change};
 pub use self::error::Error;
+#[cfg(feature = "git")]
+pub use self::git::Revision;
+pub use self::layered::{LayeredStore, Origin};
+pub use self::middleware::StoreMiddleware;
+pub use self::patch::PatchOperation;
+pub use self::path::Path;
 pub use self::schema::Schema;
+pub use self::snapshot::Snapshot;
 pub use self::source::{FileSource, Source};
-pub use self::store::Store;
+pub use self::store::{Missing, SetMode, Store};
 pub use self::value::Value;
 
+pub mod backend;
+mod diff;
 mod error;
+mod flatten;
+#[cfg(feature = "git")]
+mod git;
+mod interpolate;
+mod journal;
+mod layered;
+mod middleware;
+mod patch;
 mod path;
 mod schema;
+mod snapshot;
 pub mod source;
+#[cfg(feature = "sqlite")]
+mod sqlite;
 mod store;
 mod value;
@@ -0,0 +1,43 @@
+use crate::error::BoxError;
+use crate::path::Path;
+use crate::value::Value;
+
+/// Hooks around reads and writes of a `Store`, added with
+/// [`Store::with_middleware`](struct.Store.html#method.with_middleware).
+///
+/// Each hook receives the `Path` (relative to the store root) and `Value`, and can transform the
+/// value, or return an error to veto the read or write. Every hook does nothing by default.
+///
+/// # Example
+///
+/// ```rust
+/// use nest::{Path, StoreMiddleware, Value};
+///
+/// struct NoNulls;
+///
+/// impl StoreMiddleware for NoNulls {
+///     fn before_set(&self, path: &Path, value: Value) -> Result<Value, Box<dyn std::error::Error>> {
+///         match value {
+///             Value::Null => Err(format!("Can not set null at {}", path).into()),
+///             value => Ok(value),
+///         }
+///     }
+/// }
+/// ```
+pub trait StoreMiddleware: Send + Sync {
+    /// Called before the `value` at `path` is set (or merged or patched), returning the value to
+    /// set instead.
+    fn before_set(&self, _path: &Path, value: Value) -> Result<Value, BoxError> {
+        Ok(value)
+    }
+
+    /// Called after the `value` at `path` was set (or merged or patched).
+    fn after_set(&self, _path: &Path, _value: &Value) -> Result<(), BoxError> {
+        Ok(())
+    }
+
+    /// Called after the `value` at `path` was got, returning the value to get instead.
+    fn after_get(&self, _path: &Path, value: Value) -> Result<Value, BoxError> {
+        Ok(value)
+    }
+}
@@ -0,0 +1,302 @@
+use std::convert::TryFrom;
+use std::mem;
+
+use indexmap::IndexMap;
+use snafu::{ensure, OptionExt};
+
+use crate::error::{self, Error, Result};
+use crate::value::Value;
+
+/// A [JSON Patch (RFC 6902)](https://tools.ietf.org/html/rfc6902) operation.
+///
+/// Each `path` and `from` is a [JSON Pointer (RFC 6901)](https://tools.ietf.org/html/rfc6901),
+/// such as `"/hello/world/nest"`, relative to the path given to
+/// [`Store::patch`](struct.Store.html#method.patch).
+///
+/// `PatchOperation` also implements `TryFrom<Value>`, so operations can be read from the usual
+/// JSON form, such as `{ "op": "add", "path": "/nest", "value": "🐣" }`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PatchOperation {
+    Add { path: String, value: Value },
+    Remove { path: String },
+    Replace { path: String, value: Value },
+    Move { from: String, path: String },
+    Copy { from: String, path: String },
+    Test { path: String, value: Value },
+}
+
+impl TryFrom<Value> for PatchOperation {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Self> {
+        let invalid = || error::InvalidPatch {
+            value: value.clone(),
+        };
+        let object = value.as_object().context(invalid())?;
+        let field = |key: &str| object.get(key).context(invalid());
+        let string = |key: &str| match field(key)? {
+            Value::String(string) => Ok(string.clone()),
+            _ => invalid().fail(),
+        };
+
+        match string("op")?.as_str() {
+            "add" => Ok(PatchOperation::Add {
+                path: string("path")?,
+                value: field("value")?.clone(),
+            }),
+            "remove" => Ok(PatchOperation::Remove {
+                path: string("path")?,
+            }),
+            "replace" => Ok(PatchOperation::Replace {
+                path: string("path")?,
+                value: field("value")?.clone(),
+            }),
+            "move" => Ok(PatchOperation::Move {
+                from: string("from")?,
+                path: string("path")?,
+            }),
+            "copy" => Ok(PatchOperation::Copy {
+                from: string("from")?,
+                path: string("path")?,
+            }),
+            "test" => Ok(PatchOperation::Test {
+                path: string("path")?,
+                value: field("value")?.clone(),
+            }),
+            _ => invalid().fail(),
+        }
+    }
+}
+
+/// Apply a [JSON Merge Patch (RFC 7396)](https://tools.ietf.org/html/rfc7396) to `target`.
+pub(crate) fn merge_patch(target: Value, patch: &Value) -> Value {
+    let patch_map = match patch {
+        Value::Object(patch_map) => patch_map,
+        _ => return patch.clone(),
+    };
+
+    let mut map = match target {
+        Value::Object(map) => map,
+        _ => IndexMap::new(),
+    };
+    patch_map.iter().for_each(|(key, patch_value)| {
+        if let Value::Null = patch_value {
+            map = remove_key(mem::replace(&mut map, IndexMap::new()), key);
+            return;
+        }
+        match map.get_mut(key) {
+            Some(value) => {
+                let prev_value = mem::replace(value, Value::Null);
+                *value = merge_patch(prev_value, patch_value);
+            }
+            None => {
+                map.insert(key.clone(), merge_patch(Value::Null, patch_value));
+            }
+        }
+    });
+    Value::Object(map)
+}
+
+/// Apply [JSON Patch (RFC 6902)](https://tools.ietf.org/html/rfc6902) operations to `target`.
+pub(crate) fn patch(target: Value, operations: &[PatchOperation]) -> Result<Value> {
+    operations
+        .iter()
+        .try_fold(target, |target, operation| match operation {
+            PatchOperation::Add { path, value } => add(target, path, value.clone()),
+            PatchOperation::Remove { path } => Ok(remove(target, path)?.0),
+            PatchOperation::Replace { path, value } => replace(target, path, value.clone()),
+            PatchOperation::Move { from, path } => {
+                let (target, value) = remove(target, from)?;
+                add(target, path, value)
+            }
+            PatchOperation::Copy { from, path } => {
+                let value = get(&target, from)?.clone();
+                add(target, path, value)
+            }
+            PatchOperation::Test { path, value } => {
+                ensure!(
+                    get(&target, path)? == value,
+                    error::PatchTest {
+                        pointer: path.clone()
+                    }
+                );
+                Ok(target)
+            }
+        })
+}
+
+// keep the order of the remaining keys
+fn remove_key(map: IndexMap<String, Value>, key: &str) -> IndexMap<String, Value> {
+    map.into_iter()
+        .filter(|(next_key, _)| next_key != key)
+        .collect()
+}
+
+fn parse_pointer(pointer: &str) -> Result<Vec<String>> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+    ensure!(
+        pointer.starts_with('/'),
+        error::PatchPointer {
+            pointer: pointer.to_owned()
+        }
+    );
+    Ok(pointer[1..]
+        .split('/')
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+        .collect())
+}
+
+fn parse_index(token: &str, len: usize, pointer: &str) -> Result<usize> {
+    let index = if token == "-" {
+        Some(len)
+    } else if token.starts_with('+') || (token.starts_with('0') && token != "0") {
+        None
+    } else {
+        token.parse::<usize>().ok()
+    };
+    index.context(error::PatchPointer {
+        pointer: pointer.to_owned(),
+    })
+}
+
+fn get<'a>(target: &'a Value, pointer: &str) -> Result<&'a Value> {
+    parse_pointer(pointer)?
+        .iter()
+        .try_fold(target, |value, token| match value {
+            Value::Object(map) => map.get(token),
+            Value::Array(array) => parse_index(token, array.len(), pointer)
+                .ok()
+                .and_then(|index| array.get(index)),
+            _ => None,
+        })
+        .context(error::PatchPointer {
+            pointer: pointer.to_owned(),
+        })
+}
+
+fn add(target: Value, pointer: &str, value: Value) -> Result<Value> {
+    let tokens = parse_pointer(pointer)?;
+    add_tokens(target, &tokens, pointer, value)
+}
+
+fn add_tokens(target: Value, tokens: &[String], pointer: &str, value: Value) -> Result<Value> {
+    let (token, rest) = match tokens.split_first() {
+        Some(split) => split,
+        None => return Ok(value),
+    };
+    match target {
+        Value::Object(mut map) => {
+            if rest.is_empty() {
+                map.insert(token.clone(), value);
+            } else {
+                let nested = map.get_mut(token).context(error::PatchPointer {
+                    pointer: pointer.to_owned(),
+                })?;
+                let prev_nested = mem::replace(nested, Value::Null);
+                *nested = add_tokens(prev_nested, rest, pointer, value)?;
+            }
+            Ok(Value::Object(map))
+        }
+        Value::Array(mut array) => {
+            let index = parse_index(token, array.len(), pointer)?;
+            if rest.is_empty() {
+                ensure!(
+                    index <= array.len(),
+                    error::PatchPointer {
+                        pointer: pointer.to_owned()
+                    }
+                );
+                array.insert(index, value);
+            } else {
+                let nested = array.get_mut(index).context(error::PatchPointer {
+                    pointer: pointer.to_owned(),
+                })?;
+                let prev_nested = mem::replace(nested, Value::Null);
+                *nested = add_tokens(prev_nested, rest, pointer, value)?;
+            }
+            Ok(Value::Array(array))
+        }
+        _ => error::PatchPointer {
+            pointer: pointer.to_owned(),
+        }
+        .fail(),
+    }
+}
+
+// replace the existing value at pointer where it is, keeping the order of object keys
+fn replace(mut target: Value, pointer: &str, value: Value) -> Result<Value> {
+    let tokens = parse_pointer(pointer)?;
+    let existing = tokens
+        .iter()
+        .try_fold(&mut target, |nested, token| match nested {
+            Value::Object(map) => map.get_mut(token),
+            Value::Array(array) => {
+                let len = array.len();
+                parse_index(token, len, pointer)
+                    .ok()
+                    .and_then(move |index| array.get_mut(index))
+            }
+            _ => None,
+        })
+        .context(error::PatchPointer {
+            pointer: pointer.to_owned(),
+        })?;
+    *existing = value;
+    Ok(target)
+}
+
+// returns the target without the value at pointer, and the removed value
+fn remove(target: Value, pointer: &str) -> Result<(Value, Value)> {
+    let tokens = parse_pointer(pointer)?;
+    ensure!(
+        !tokens.is_empty(),
+        error::PatchPointer {
+            pointer: pointer.to_owned()
+        }
+    );
+    remove_tokens(target, &tokens, pointer)
+}
+
+fn remove_tokens(target: Value, tokens: &[String], pointer: &str) -> Result<(Value, Value)> {
+    let (token, rest) = tokens.split_first().unwrap();
+    match target {
+        Value::Object(mut map) => {
+            let nested = map.get_mut(token).context(error::PatchPointer {
+                pointer: pointer.to_owned(),
+            })?;
+            if rest.is_empty() {
+                let removed = mem::replace(nested, Value::Null);
+                Ok((Value::Object(remove_key(map, token)), removed))
+            } else {
+                let prev_nested = mem::replace(nested, Value::Null);
+                let (next_nested, removed) = remove_tokens(prev_nested, rest, pointer)?;
+                *nested = next_nested;
+                Ok((Value::Object(map), removed))
+            }
+        }
+        Value::Array(mut array) => {
+            let index = parse_index(token, array.len(), pointer)?;
+            ensure!(
+                index < array.len(),
+                error::PatchPointer {
+                    pointer: pointer.to_owned()
+                }
+            );
+            if rest.is_empty() {
+                let removed = array.remove(index);
+                Ok((Value::Array(array), removed))
+            } else {
+                let prev_nested = mem::replace(&mut array[index], Value::Null);
+                let (next_nested, removed) = remove_tokens(prev_nested, rest, pointer)?;
+                array[index] = next_nested;
+                Ok((Value::Array(array), removed))
+            }
+        }
+        _ => error::PatchPointer {
+            pointer: pointer.to_owned(),
+        }
+        .fail(),
+    }
+}
@@ -2,10 +2,23 @@ use std::convert::Into;
 use std::fmt;
 use std::path;
 
-#[derive(Debug, Clone)]
+/// A path to a value within a Nest, as a list of keys.
+///
+/// `Path` implements `From` for arrays and vectors of strings, so most functions accept a path
+/// like `&["hello", "world", "nest"]`.
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct Path(Vec<String>);
 
 impl Path {
+    /// Parse a path from keys separated by `/`, such as `hello/world/nest`.
+    pub fn parse(string: &str) -> Self {
+        if string.is_empty() {
+            Path::default()
+        } else {
+            Path(string.split('/').map(String::from).collect())
+        }
+    }
+
     pub fn len(&self) -> usize {
         self.0.len()
     }
@@ -30,6 +43,10 @@ impl Path {
         Path(self.0[num..self.len()].to_vec())
     }
 
+    pub fn starts_with(&self, other: &Path) -> bool {
+        self.0.starts_with(&other.0)
+    }
+
     pub fn append(&self, item: &str) -> Self {
         let mut vec = Vec::new();
         vec.extend(self.0.iter().cloned());
@@ -37,8 +54,12 @@ impl Path {
         Path(vec)
     }
 
+    pub fn iter(&self) -> impl Iterator<Item = &String> {
+        self.0.iter()
+    }
+
     pub fn to_path(&self) -> path::PathBuf {
-        path::PathBuf::from(self.0.join(&path::MAIN_SEPARATOR.to_string()))
+        self.0.iter().collect()
     }
 }
 
@@ -1,17 +1,59 @@
+use std::collections::btree_map::Entry;
 use std::collections::BTreeMap;
 use std::convert::{TryFrom, TryInto};
+use std::fs;
+use std::path;
 
+use indexmap::IndexMap;
+use log::{info, warn};
 use serde_json as json;
-use snafu::OptionExt;
+use snafu::{ensure, OptionExt, ResultExt};
 
+use crate::backend::LocalFs;
 use crate::error::{self, Error, Result};
-use crate::source::{Source, SOURCES};
+use crate::path::Path;
+use crate::source::{list_directory, strip_extension, Source, SOURCES};
 use crate::value::Value;
 
+// `{ "$include": "../shared/.nest.json" }`, relative to the including schema file
+const INCLUDE_KEY: &str = "$include";
+// `{ "$link": "other/path" }`, relative to the store root
+const LINK_KEY: &str = "$link";
+// the kind of leaf, such as `{ "$format": "json" }`
+const FORMAT_KEY: &str = "$format";
+const TREE_KEY: &str = "$tree";
+const SPLIT_KEY: &str = "$split";
+// leaf options, such as `{ "$format": "json", "$default": { ... } }`
+const DEFAULT_KEY: &str = "$default";
+pub(crate) const ENCRYPT_KEY: &str = "$encrypt";
+pub(crate) const KEY_FILE_KEY: &str = "$key";
+
 /// The mapping of your data structures with the filesystem (files and directories).
 ///
 /// A `Schema` is a tree with `Schema::Directory` as branches and sources (e.g. `Schema::Source`) as leaves.
 ///
+/// A source leaf is written as the id of the source, such as `"json"`, or as
+/// `{ "$format": "json" }`.
+///
+/// A leaf may also be a `Schema::Tree`, written as `{ "$tree": "json" }`, where every file under the
+/// directory is a source and every subdirectory is a nested object, discovered at read time.
+///
+/// Or a leaf may be a `Schema::Split`, written as `{ "$split": "json" }`, where an object is stored
+/// as a directory with a source for each top-level key.
+///
+/// Any leaf may declare a default value with `Schema::Default`, written as
+/// `{ "$format": "json", "$default": { ... } }` (or `{ "$tree": "json", "$default": { ... } }`).
+///
+/// Keys starting with `$` are reserved for the schema, so any other object is a directory, even
+/// with keys such as `"tree"` or `"default"`.
+///
+/// A leaf may also be a `Schema::Link`, written as `{ "$link": "other/path" }`, an alias for the
+/// value at another path in the nest (relative to the store root).
+///
+/// A directory may splice in another schema file with `{ "$include": "../shared/.nest.json" }`,
+/// alongside any other keys, where the path is relative to the including schema file (see
+/// [`Schema::from_file`](#method.from_file)).
+///
 /// `Schema` also implements `From<serde_json::Value>`, so you can use the [`serde_json::json`] macro
 /// to conveniently create schemas.
 ///
@@ -22,43 +64,146 @@ use crate::value::Value;
 pub enum Schema {
     Directory(BTreeMap<String, Schema>),
     Source(Box<dyn Source>),
+    Tree(Box<dyn Source>),
+    Split(Box<dyn Source>),
+    Default(Box<Schema>, Value),
+    Link(Path),
+}
+
+impl Schema {
+    /// Infer a `Schema` from the existing files and directories at `root`.
+    ///
+    /// Files are recognized by extension using the registered sources, directories become nested
+    /// schemas, and anything else (including hidden files and empty directories) is ignored.
+    pub fn infer<A>(root: A) -> Result<Schema>
+    where
+        A: AsRef<path::Path>,
+    {
+        let root = root.as_ref();
+        info!("nest::Schema::infer({:?})", root);
+
+        infer_directory(root)
+    }
+
+    /// Read a `Schema` from a JSON file, such as `.nest.json`.
+    ///
+    /// Any `$include` is relative to the directory of the file including it.
+    pub fn from_file<A>(file_path: A) -> Result<Schema>
+    where
+        A: AsRef<path::Path>,
+    {
+        let file_path = file_path.as_ref();
+        info!("nest::Schema::from_file({:?})", file_path);
+
+        read_schema_file(file_path, &mut Vec::new())
+    }
 }
 
 impl TryFrom<Value> for Schema {
     type Error = Error;
 
+    /// Any `$include` is relative to the current directory.
     fn try_from(value: Value) -> Result<Self> {
-        match value {
-            Value::Object(object) => {
-                let mut map = BTreeMap::new();
-                object
-                    .into_iter()
-                    .try_for_each(|(key, value)| -> Result<()> {
-                        let schema = Self::try_from(value)?;
-                        map.insert(key, schema);
-                        Ok(())
-                    })?;
-                Ok(Schema::Directory(map))
+        schema_from_value(value, path::Path::new(""), &mut Vec::new())
+    }
+}
+
+// `includes` are the schema files being read, to catch includes of each other
+fn read_schema_file(file_path: &path::Path, includes: &mut Vec<path::PathBuf>) -> Result<Schema> {
+    let canonical_path =
+        fs::canonicalize(file_path).context(error::ReadSchema { path: file_path })?;
+    ensure!(
+        !includes.contains(&canonical_path),
+        error::IncludeCycle { path: file_path }
+    );
+
+    let string = fs::read_to_string(file_path).context(error::ReadSchema { path: file_path })?;
+    let json_value: json::Value = json::from_str(&string)
+        .map_err(|err| -> Box<dyn std::error::Error> { Box::new(err) })
+        .context(error::Deserialize {
+            kind: "json",
+            path: file_path,
+            string: string.clone(),
+        })?;
+
+    let directory_path = file_path.parent().unwrap_or_else(|| path::Path::new(""));
+    includes.push(canonical_path);
+    let schema = schema_from_value(json_value.into(), directory_path, includes);
+    includes.pop();
+    schema
+}
+
+fn schema_from_value(
+    value: Value,
+    directory_path: &path::Path,
+    includes: &mut Vec<path::PathBuf>,
+) -> Result<Schema> {
+    match value {
+        Value::Object(object) => {
+            if let Some(schema) = link_from_object(&object)? {
+                return Ok(schema);
             }
-            Value::String(string) => {
-                let source: Box<dyn Source> = SOURCES
-                    .iter()
-                    .find_map(|source| {
-                        if string == source.id() {
-                            Some(source.clone())
-                        } else {
-                            None
-                        }
-                    })
-                    .context(error::InvalidSchema {
-                        value: Value::String(string),
-                    })?;
-                Ok(Schema::Source(source))
+            if let Some(schema) = leaf_from_object(&object)? {
+                return Ok(schema);
             }
-            _ => Err(Error::InvalidSchema {
-                value: value.clone(),
-            }),
+
+            // keys alongside an include are added to (or replace those in) the included directory
+            let mut map = match object.get(INCLUDE_KEY) {
+                Some(Value::String(include_path)) => {
+                    match read_schema_file(&directory_path.join(include_path), includes)? {
+                        Schema::Directory(map) => map,
+                        schema if object.len() == 1 => return Ok(schema),
+                        _ => {
+                            return error::InvalidSchema {
+                                value: Value::Object(object),
+                            }
+                            .fail()
+                        }
+                    }
+                }
+                Some(value) => {
+                    return error::InvalidSchema {
+                        value: value.clone(),
+                    }
+                    .fail()
+                }
+                None => BTreeMap::new(),
+            };
+            object
+                .into_iter()
+                .filter(|(key, _)| key != INCLUDE_KEY)
+                .try_for_each(|(key, value)| -> Result<()> {
+                    ensure!(
+                        !key.starts_with('$'),
+                        error::InvalidSchema {
+                            value: Value::String(key),
+                        }
+                    );
+                    let schema = schema_from_value(value, directory_path, includes)?;
+                    map.insert(key, schema);
+                    Ok(())
+                })?;
+            Ok(Schema::Directory(map))
         }
+        Value::String(string) => Ok(Schema::Source(find_source(&string)?)),
+        _ => error::InvalidSchema {
+            value: value.clone(),
+        }
+        .fail(),
+    }
+}
+
+// a link object is `{ "$link": "other/path" }`
+fn link_from_object(object: &IndexMap<String, Value>) -> Result<Option<Schema>> {
+    match object.get(LINK_KEY) {
+        Some(Value::String(link_path)) if object.len() == 1 => {
+            Ok(Some(Schema::Link(Path::parse(link_path))))
+        }
+        Some(_) => error::InvalidSchema {
+            value: Value::Object(object.clone()),
+        }
+        .fail(),
+        None => Ok(None),
     }
 }
 
@@ -70,3 +215,192 @@ impl TryFrom<json::Value> for Schema {
         value.try_into()
     }
 }
+
+// a leaf object is one of `{ "$format": id }`, `{ "$tree": id }` or `{ "$split": id }`,
+// optionally with a `"$default"` value and `"$encrypt"` (and `"$key"`) options,
+// otherwise the object is a directory
+fn leaf_from_object(object: &IndexMap<String, Value>) -> Result<Option<Schema>> {
+    let is_leaf_key = |key: &str| key == FORMAT_KEY || key == TREE_KEY || key == SPLIT_KEY;
+    let is_option_key = |key: &str| key == DEFAULT_KEY || key == ENCRYPT_KEY || key == KEY_FILE_KEY;
+    if !object
+        .keys()
+        .any(|key| is_leaf_key(key) || is_option_key(key))
+    {
+        return Ok(None);
+    }
+
+    // a leaf has exactly one kind and nothing but options
+    let invalid = || error::InvalidSchema {
+        value: Value::Object(object.clone()),
+    };
+    ensure!(
+        object
+            .keys()
+            .all(|key| is_leaf_key(key) || is_option_key(key)),
+        invalid()
+    );
+    let mut leaves = object.iter().filter(|(key, _)| is_leaf_key(key));
+    let (kind, id) = match (leaves.next(), leaves.next()) {
+        (Some((kind, Value::String(id))), None) => (kind, id),
+        _ => return invalid().fail(),
+    };
+
+    let source = match object.get(ENCRYPT_KEY) {
+        Some(scheme) => encrypted_source(find_source(id)?, scheme, object.get(KEY_FILE_KEY))?,
+        None if object.contains_key(KEY_FILE_KEY) => return invalid().fail(),
+        None => find_source(id)?,
+    };
+    let schema = match kind.as_str() {
+        TREE_KEY => Schema::Tree(source),
+        SPLIT_KEY => Schema::Split(source),
+        _ => Schema::Source(source),
+    };
+
+    Ok(Some(match object.get(DEFAULT_KEY) {
+        Some(default) => Schema::Default(Box::new(schema), default.clone()),
+        None => schema,
+    }))
+}
+
+#[cfg(feature = "encryption")]
+fn encrypted_source(
+    source: Box<dyn Source>,
+    scheme: &Value,
+    key_file: Option<&Value>,
+) -> Result<Box<dyn Source>> {
+    use crate::source::encrypted::{EncryptMode, Encrypted, SCHEME, VALUES_SCHEME};
+
+    let key_file = match key_file {
+        Some(Value::String(key_file)) => Some(path::PathBuf::from(key_file)),
+        None => None,
+        Some(value) => {
+            return error::InvalidSchema {
+                value: value.clone(),
+            }
+            .fail()
+        }
+    };
+    let mode = match scheme {
+        Value::String(scheme) if scheme == SCHEME => EncryptMode::File,
+        Value::String(scheme) if scheme == VALUES_SCHEME => EncryptMode::Values,
+        _ => {
+            return error::InvalidSchema {
+                value: scheme.clone(),
+            }
+            .fail()
+        }
+    };
+    Ok(Box::new(Encrypted::new(source, mode, key_file)))
+}
+
+// without the `encryption` feature, encrypted leaves are invalid
+#[cfg(not(feature = "encryption"))]
+fn encrypted_source(
+    _source: Box<dyn Source>,
+    scheme: &Value,
+    _key_file: Option<&Value>,
+) -> Result<Box<dyn Source>> {
+    error::InvalidSchema {
+        value: scheme.clone(),
+    }
+    .fail()
+}
+
+fn find_source(id: &str) -> Result<Box<dyn Source>> {
+    SOURCES
+        .iter()
+        .find_map(|source| {
+            if id == source.id() {
+                Some(source.clone())
+            } else {
+                None
+            }
+        })
+        .context(error::InvalidSchema {
+            value: Value::String(id.to_owned()),
+        })
+}
+
+fn infer_directory(directory_path: &path::Path) -> Result<Schema> {
+    let mut map = BTreeMap::new();
+    list_directory(&LocalFs, directory_path)?
+        .into_iter()
+        .try_for_each(|(file_name, entry_path)| -> Result<()> {
+            // keys starting with `$` are reserved for the schema
+            if file_name.starts_with('$') {
+                warn!("ignoring reserved name in schema at {:?}", entry_path);
+                return Ok(());
+            }
+            if entry_path.is_dir() {
+                match infer_directory(&entry_path)? {
+                    Schema::Directory(ref nested_map) if nested_map.is_empty() => {}
+                    schema => {
+                        map.insert(file_name, schema);
+                    }
+                }
+            } else {
+                let found = SOURCES.iter().find_map(|source| {
+                    strip_extension(&file_name, &source.extensions()).map(|key| (key, source))
+                });
+                if let Some((key, source)) = found {
+                    match map.entry(key) {
+                        // a directory (sorted first) or another source already has this key
+                        Entry::Occupied(_) => {
+                            warn!("ignoring conflicting file in schema at {:?}", entry_path);
+                        }
+                        Entry::Vacant(entry) => {
+                            entry.insert(Schema::Source(source.clone()));
+                        }
+                    }
+                }
+            }
+            Ok(())
+        })?;
+    Ok(Schema::Directory(map))
+}
+
+impl From<&Schema> for Value {
+    fn from(schema: &Schema) -> Value {
+        match schema {
+            Schema::Directory(map) => Value::Object(
+                map.iter()
+                    .map(|(key, schema)| (key.clone(), Self::from(schema)))
+                    .collect(),
+            ),
+            Schema::Source(source) => {
+                if source.options().is_empty() {
+                    Value::String(source.id())
+                } else {
+                    leaf_value(FORMAT_KEY, &**source)
+                }
+            }
+            Schema::Tree(source) => leaf_value(TREE_KEY, &**source),
+            Schema::Split(source) => leaf_value(SPLIT_KEY, &**source),
+            Schema::Default(schema, default) => {
+                let mut map = match Self::from(&**schema) {
+                    Value::String(id) => leaf_map(FORMAT_KEY, id),
+                    Value::Object(map) => map,
+                    _ => IndexMap::new(),
+                };
+                map.insert(DEFAULT_KEY.into(), default.clone());
+                Value::Object(map)
+            }
+            Schema::Link(path) => {
+                let keys: Vec<&str> = path.iter().map(String::as_str).collect();
+                Value::Object(leaf_map(LINK_KEY, keys.join("/")))
+            }
+        }
+    }
+}
+
+fn leaf_value(kind: &str, source: &dyn Source) -> Value {
+    let mut map = leaf_map(kind, source.id());
+    map.extend(source.options());
+    Value::Object(map)
+}
+
+fn leaf_map(kind: &str, id: String) -> IndexMap<String, Value> {
+    let mut map = IndexMap::new();
+    map.insert(kind.to_owned(), Value::String(id));
+    map
+}
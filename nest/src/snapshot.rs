@@ -0,0 +1,138 @@
+use std::collections::BTreeMap;
+#[cfg(feature = "archive")]
+use std::io::{Read, Write};
+use std::path;
+
+#[cfg(feature = "archive")]
+use snafu::{ensure, OptionExt, ResultExt};
+
+#[cfg(feature = "archive")]
+use crate::error::{self, Result};
+use crate::path::Path;
+
+#[cfg(feature = "archive")]
+// the tarball entry describing the snapshot, hidden like `.nest.json`
+const METADATA_ENTRY: &str = ".nest-snapshot.json";
+
+/// The bytes of every source (file) under a path in a `Store`, as returned by
+/// [`Store::snapshot`](struct.Store.html#method.snapshot).
+///
+/// Snapshots are held in memory, and (with the `archive` feature) can be saved and loaded as a
+/// tarball with [`write_tar`](#method.write_tar) and [`read_tar`](#method.read_tar).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Snapshot {
+    path: Path,
+    files: BTreeMap<path::PathBuf, Vec<u8>>,
+}
+
+impl Snapshot {
+    pub(crate) fn new(path: Path, files: BTreeMap<path::PathBuf, Vec<u8>>) -> Self {
+        Snapshot { path, files }
+    }
+
+    /// The path within the `Store` that was captured.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The captured sources (files), by file path relative to the `Store` root.
+    pub fn files(&self) -> &BTreeMap<path::PathBuf, Vec<u8>> {
+        &self.files
+    }
+
+    /// Write the snapshot as a tarball.
+    #[cfg(feature = "archive")]
+    pub fn write_tar<W>(&self, writer: W) -> Result<()>
+    where
+        W: Write,
+    {
+        let mut builder = tar::Builder::new(writer);
+
+        let keys: Vec<&str> = self.path.iter().map(String::as_str).collect();
+        let metadata = serde_json::Value::from(keys).to_string();
+        append_entry(
+            &mut builder,
+            path::Path::new(METADATA_ENTRY),
+            metadata.as_bytes(),
+        )?;
+
+        self.files
+            .iter()
+            .try_for_each(|(file_path, bytes)| append_entry(&mut builder, file_path, bytes))?;
+
+        builder
+            .into_inner()
+            .and_then(|mut writer| writer.flush())
+            .context(error::WriteSnapshot)
+    }
+
+    /// Read a snapshot from a tarball written by [`write_tar`](#method.write_tar).
+    #[cfg(feature = "archive")]
+    pub fn read_tar<R>(reader: R) -> Result<Snapshot>
+    where
+        R: Read,
+    {
+        let mut archive = tar::Archive::new(reader);
+        let mut path = None;
+        let mut files = BTreeMap::new();
+
+        for entry in archive.entries().context(error::ReadSnapshot)? {
+            let mut entry = entry.context(error::ReadSnapshot)?;
+            let file_path = entry.path().context(error::ReadSnapshot)?.into_owned();
+            let mut bytes = Vec::new();
+            entry.read_to_end(&mut bytes).context(error::ReadSnapshot)?;
+
+            if file_path == path::Path::new(METADATA_ENTRY) {
+                path = Some(
+                    parse_metadata(&bytes).context(error::InvalidSnapshot { path: file_path })?,
+                );
+                continue;
+            }
+
+            // never restore outside of the store root
+            ensure!(
+                file_path.is_relative()
+                    && !file_path
+                        .components()
+                        .any(|component| component == path::Component::ParentDir),
+                error::InvalidSnapshot { path: file_path }
+            );
+            files.insert(file_path, bytes);
+        }
+
+        let path = path.context(error::InvalidSnapshot {
+            path: METADATA_ENTRY,
+        })?;
+        Ok(Snapshot { path, files })
+    }
+}
+
+#[cfg(feature = "archive")]
+fn append_entry<W>(
+    builder: &mut tar::Builder<W>,
+    file_path: &path::Path,
+    bytes: &[u8],
+) -> Result<()>
+where
+    W: Write,
+{
+    let mut header = tar::Header::new_gnu();
+    header.set_size(bytes.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder
+        .append_data(&mut header, file_path, bytes)
+        .context(error::WriteSnapshot)
+}
+
+// the metadata is the snapshot path as an array of keys
+#[cfg(feature = "archive")]
+fn parse_metadata(bytes: &[u8]) -> Option<Path> {
+    let value: serde_json::Value = serde_json::from_slice(bytes).ok()?;
+    let keys = value
+        .as_array()?
+        .iter()
+        .map(|key| key.as_str().map(String::from))
+        .collect::<Option<Vec<String>>>()?;
+    Some((&keys).into())
+}
@@ -0,0 +1,449 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::env;
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use snafu::{OptionExt, ResultExt};
+
+use super::{append_extension, find_file, read_file, write_file, Source};
+use crate::backend::Backend;
+use crate::error::{self, Error};
+use crate::schema::{ENCRYPT_KEY, KEY_FILE_KEY};
+use crate::value::Value;
+
+/// The environment variable with the path to the key file, if the schema has no `"$key"`.
+pub const KEY_FILE_ENV: &str = "NEST_KEY_FILE";
+
+/// The encryption scheme for whole sources (files), as written in the schema:
+/// `{ "$format": "yaml", "$encrypt": "aes-gcm" }`.
+pub const SCHEME: &str = "aes-gcm";
+
+/// The encryption scheme for each value within sources (files), as written in the schema:
+/// `{ "$format": "yaml", "$encrypt": "aes-gcm-values" }`.
+pub const VALUES_SCHEME: &str = "aes-gcm-values";
+
+// appended to the extension of the wrapped source, e.g. `secrets.yaml.enc`
+const EXTENSION: &str = "enc";
+const PREFIX: &str = "nest:aes-gcm:";
+// an encrypted value is `ENC[aes-gcm,salt:...,iv:...,data:...]`
+const VALUE_PREFIX: &str = "ENC[aes-gcm,";
+const VALUE_SUFFIX: &str = "]";
+const SALT_LENGTH: usize = 16;
+const NONCE_LENGTH: usize = 12;
+const ITERATIONS: u32 = 100_000;
+
+/// A source (file) encrypted with AES-256-GCM, using a key derived from the passphrase in a
+/// local key file.
+///
+/// With `EncryptMode::Values`, only the values within the source (file) are encrypted, so keys
+/// stay readable, and unchanged values keep their ciphertext when the source (file) is written.
+/// Each value is bound to its path within the source (file), so values can't be swapped.
+///
+/// The key file is the `"$key"` in the schema, such as
+/// `{ "$format": "yaml", "$encrypt": "aes-gcm", "$key": "/home/dinosaur/.nest-key" }`, otherwise
+/// the path in the `NEST_KEY_FILE` environment variable.
+#[derive(Clone, Debug)]
+pub struct Encrypted {
+    source: Box<dyn Source>,
+    mode: EncryptMode,
+    key_file: Option<PathBuf>,
+}
+
+/// What an [`Encrypted`](struct.Encrypted.html) source encrypts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EncryptMode {
+    /// The whole source (file), as `"aes-gcm"`.
+    File,
+    /// Each value within the source (file), as `"aes-gcm-values"`.
+    Values,
+}
+
+impl Encrypted {
+    pub fn new(source: Box<dyn Source>, mode: EncryptMode, key_file: Option<PathBuf>) -> Self {
+        Encrypted {
+            source,
+            mode,
+            key_file,
+        }
+    }
+
+    fn parse_file(&self, path: &Path, string: &str) -> Result<Value, Error> {
+        let passphrase = read_key(&self.key_file)?;
+        let decrypt = || -> Option<Vec<u8>> {
+            let mut parts = string.trim().trim_start_matches(PREFIX).split(':');
+            let salt = BASE64.decode(parts.next()?).ok()?;
+            let nonce = BASE64.decode(parts.next()?).ok()?;
+            let ciphertext = BASE64.decode(parts.next()?).ok()?;
+            Cipher::new(&passphrase, &salt).decrypt(&nonce, &ciphertext, &[])
+        };
+        if !string.starts_with(PREFIX) {
+            return error::Decrypt { path }.fail();
+        }
+        let plaintext = decrypt()
+            .and_then(|plaintext| String::from_utf8(plaintext).ok())
+            .context(error::Decrypt { path })?;
+        self.source.parse(path, &plaintext)
+    }
+
+    fn stringify_file(&self, path: &Path, value: &Value) -> Result<String, Error> {
+        let plaintext = self.source.stringify(path, value)?;
+        let salt = random_bytes(SALT_LENGTH);
+        let (nonce, ciphertext) = Cipher::new(&read_key(&self.key_file)?, &salt)
+            .encrypt(plaintext.as_bytes(), &[])
+            .context(error::Encrypt { path })?;
+        Ok(format!(
+            "{}{}:{}:{}\n",
+            PREFIX,
+            BASE64.encode(&salt),
+            BASE64.encode(&nonce),
+            BASE64.encode(&ciphertext)
+        ))
+    }
+
+    // `previous` is the value as stored before, with encrypted values to keep if unchanged
+    fn stringify_values(
+        &self,
+        path: &Path,
+        value: &Value,
+        previous: Option<&Value>,
+    ) -> Result<String, Error> {
+        let mut ciphers = Ciphers::new(read_key(&self.key_file)?);
+        // one salt (and so one derived key) per source (file), kept from the previous values
+        let salt = previous
+            .and_then(find_salt)
+            .unwrap_or_else(|| random_bytes(SALT_LENGTH));
+        let encrypted_value = encrypt_values(value, previous, &mut ciphers, &salt, &mut Vec::new())
+            .context(error::Encrypt { path })?;
+        self.source.stringify(path, &encrypted_value)
+    }
+}
+
+impl Source for Encrypted {
+    fn id(&self) -> String {
+        self.source.id()
+    }
+
+    fn extensions(&self) -> Vec<String> {
+        match self.mode {
+            EncryptMode::File => self
+                .source
+                .extensions()
+                .into_iter()
+                .map(|extension| format!("{}.{}", extension, EXTENSION))
+                .collect(),
+            EncryptMode::Values => self.source.extensions(),
+        }
+    }
+
+    fn file_path(&self, backend: &dyn Backend, path: PathBuf) -> PathBuf {
+        let extensions = self.extensions();
+        find_file(backend, &path, &extensions)
+            .unwrap_or_else(|| append_extension(&path, &extensions[0]))
+    }
+
+    fn parse(&self, path: &Path, string: &str) -> Result<Value, Error> {
+        match self.mode {
+            EncryptMode::File => self.parse_file(path, string),
+            EncryptMode::Values => {
+                let value = self.source.parse(path, string)?;
+                let mut ciphers = Ciphers::new(read_key(&self.key_file)?);
+                decrypt_values(value, &mut ciphers, &mut Vec::new())
+                    .context(error::Decrypt { path })
+            }
+        }
+    }
+
+    fn stringify(&self, path: &Path, value: &Value) -> Result<String, Error> {
+        match self.mode {
+            EncryptMode::File => self.stringify_file(path, value),
+            EncryptMode::Values => self.stringify_values(path, value, None),
+        }
+    }
+
+    fn read(&self, backend: &dyn Backend, path: PathBuf) -> Result<Value, Error> {
+        let file_path = self.file_path(backend, path.clone());
+        let file_string =
+            read_file(backend, &file_path).context(error::ReadSource { path: path.clone() })?;
+        self.parse(&path, &file_string)
+    }
+
+    fn write(&self, backend: &dyn Backend, path: PathBuf, value: &Value) -> Result<(), Error> {
+        let file_path = self.file_path(backend, path.clone());
+        let file_string = match self.mode {
+            EncryptMode::File => self.stringify_file(&path, value)?,
+            EncryptMode::Values => {
+                let previous = read_file(backend, &file_path)
+                    .ok()
+                    .and_then(|file_string| self.source.parse(&path, &file_string).ok());
+                self.stringify_values(&path, value, previous.as_ref())?
+            }
+        };
+        write_file(backend, &file_path, file_string).context(error::WriteSource { path })
+    }
+
+    fn options(&self) -> Vec<(String, Value)> {
+        let scheme = match self.mode {
+            EncryptMode::File => SCHEME,
+            EncryptMode::Values => VALUES_SCHEME,
+        };
+        let mut options = vec![(ENCRYPT_KEY.to_owned(), Value::String(scheme.to_owned()))];
+        if let Some(ref key_file) = self.key_file {
+            options.push((
+                KEY_FILE_KEY.to_owned(),
+                Value::String(key_file.to_string_lossy().into_owned()),
+            ));
+        }
+        options
+    }
+
+    fn rekey(&self, key_file: &Path) -> Option<Box<dyn Source>> {
+        Some(Box::new(Encrypted {
+            source: self.source.clone(),
+            mode: self.mode,
+            key_file: Some(key_file.to_path_buf()),
+        }))
+    }
+}
+
+// an AES-256-GCM key derived from a passphrase and salt
+struct Cipher(Aes256Gcm);
+
+impl Cipher {
+    fn new(passphrase: &str, salt: &[u8]) -> Self {
+        let mut key = [0u8; 32];
+        pbkdf2::pbkdf2_hmac::<sha2::Sha256>(passphrase.as_bytes(), salt, ITERATIONS, &mut key);
+        Cipher(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)))
+    }
+
+    // returns the random nonce and the ciphertext, authenticating `aad` along with it
+    fn encrypt(&self, plaintext: &[u8], aad: &[u8]) -> Option<(Vec<u8>, Vec<u8>)> {
+        let nonce = random_bytes(NONCE_LENGTH);
+        let payload = Payload {
+            msg: plaintext,
+            aad,
+        };
+        let ciphertext = self.0.encrypt(Nonce::from_slice(&nonce), payload).ok()?;
+        Some((nonce, ciphertext))
+    }
+
+    fn decrypt(&self, nonce: &[u8], ciphertext: &[u8], aad: &[u8]) -> Option<Vec<u8>> {
+        if nonce.len() != NONCE_LENGTH {
+            return None;
+        }
+        let payload = Payload {
+            msg: ciphertext,
+            aad,
+        };
+        self.0.decrypt(Nonce::from_slice(nonce), payload).ok()
+    }
+}
+
+// ciphers by salt, so each key is only derived once
+struct Ciphers {
+    passphrase: String,
+    ciphers: HashMap<Vec<u8>, Cipher>,
+}
+
+impl Ciphers {
+    fn new(passphrase: String) -> Self {
+        Ciphers {
+            passphrase,
+            ciphers: HashMap::new(),
+        }
+    }
+
+    fn get(&mut self, salt: &[u8]) -> &Cipher {
+        let passphrase = &self.passphrase;
+        self.ciphers
+            .entry(salt.to_vec())
+            .or_insert_with(|| Cipher::new(passphrase, salt))
+    }
+}
+
+// the associated data for the value at `path` within a source (file), as a json array of keys
+// (and array indexes), so an encrypted value can't be moved to another path
+fn value_aad(path: &[String]) -> Vec<u8> {
+    serde_json::to_vec(path).unwrap_or_default()
+}
+
+// encrypt each value (as json) at its `path`, keeping the previous encrypted value if unchanged
+fn encrypt_values(
+    value: &Value,
+    previous: Option<&Value>,
+    ciphers: &mut Ciphers,
+    salt: &[u8],
+    path: &mut Vec<String>,
+) -> Option<Value> {
+    match value {
+        Value::Object(map) => {
+            let previous_map = previous.and_then(Value::as_object);
+            map.iter()
+                .map(|(key, nested_value)| {
+                    let previous_value = previous_map.and_then(|previous| previous.get(key));
+                    path.push(key.clone());
+                    let encrypted =
+                        encrypt_values(nested_value, previous_value, ciphers, salt, path);
+                    path.pop();
+                    Some((key.clone(), encrypted?))
+                })
+                .collect::<Option<_>>()
+                .map(Value::Object)
+        }
+        Value::Array(array) => {
+            let previous_array = match previous {
+                Some(Value::Array(previous_array)) => Some(previous_array),
+                _ => None,
+            };
+            array
+                .iter()
+                .enumerate()
+                .map(|(index, item)| {
+                    let previous_item = previous_array.and_then(|previous| previous.get(index));
+                    path.push(index.to_string());
+                    let encrypted = encrypt_values(item, previous_item, ciphers, salt, path);
+                    path.pop();
+                    encrypted
+                })
+                .collect::<Option<_>>()
+                .map(Value::Array)
+        }
+        _ => {
+            let plaintext = serde_json::Value::try_from(value.clone()).ok()?.to_string();
+            let aad = value_aad(path);
+            if let Some(Value::String(previous)) = previous {
+                if let Some(parts) = parse_value(previous) {
+                    let previous_plaintext =
+                        ciphers
+                            .get(&parts.salt)
+                            .decrypt(&parts.nonce, &parts.ciphertext, &aad);
+                    if parts.salt == salt
+                        && previous_plaintext.as_deref() == Some(plaintext.as_bytes())
+                    {
+                        return Some(Value::String(previous.clone()));
+                    }
+                }
+            }
+            let (nonce, ciphertext) = ciphers.get(salt).encrypt(plaintext.as_bytes(), &aad)?;
+            Some(Value::String(format!(
+                "{}salt:{},iv:{},data:{}{}",
+                VALUE_PREFIX,
+                BASE64.encode(salt),
+                BASE64.encode(&nonce),
+                BASE64.encode(&ciphertext),
+                VALUE_SUFFIX
+            )))
+        }
+    }
+}
+
+// decrypt each encrypted value at its `path`, leaving any other values as they are
+fn decrypt_values(value: Value, ciphers: &mut Ciphers, path: &mut Vec<String>) -> Option<Value> {
+    match value {
+        Value::Object(map) => map
+            .into_iter()
+            .map(|(key, nested_value)| {
+                path.push(key.clone());
+                let decrypted = decrypt_values(nested_value, ciphers, path);
+                path.pop();
+                Some((key, decrypted?))
+            })
+            .collect::<Option<_>>()
+            .map(Value::Object),
+        Value::Array(array) => array
+            .into_iter()
+            .enumerate()
+            .map(|(index, item)| {
+                path.push(index.to_string());
+                let decrypted = decrypt_values(item, ciphers, path);
+                path.pop();
+                decrypted
+            })
+            .collect::<Option<_>>()
+            .map(Value::Array),
+        Value::String(string) => {
+            if !string.starts_with(VALUE_PREFIX) {
+                return Some(Value::String(string));
+            }
+            let parts = parse_value(&string)?;
+            let plaintext = ciphers.get(&parts.salt).decrypt(
+                &parts.nonce,
+                &parts.ciphertext,
+                &value_aad(path),
+            )?;
+            let json_value: serde_json::Value = serde_json::from_slice(&plaintext).ok()?;
+            Some(json_value.into())
+        }
+        value => Some(value),
+    }
+}
+
+// the salt of the first encrypted value within `value`
+fn find_salt(value: &Value) -> Option<Vec<u8>> {
+    match value {
+        Value::Object(map) => map.values().find_map(find_salt),
+        Value::Array(array) => array.iter().find_map(find_salt),
+        Value::String(string) => parse_value(string).map(|parts| parts.salt),
+        _ => None,
+    }
+}
+
+// the fields of an encrypted value
+struct EncryptedValue {
+    salt: Vec<u8>,
+    nonce: Vec<u8>,
+    ciphertext: Vec<u8>,
+}
+
+// the fields of an encrypted value, `None` if not an encrypted value
+fn parse_value(string: &str) -> Option<EncryptedValue> {
+    if !string.starts_with(VALUE_PREFIX) || !string.ends_with(VALUE_SUFFIX) {
+        return None;
+    }
+    let fields = &string[VALUE_PREFIX.len()..string.len() - VALUE_SUFFIX.len()];
+    let mut salt = None;
+    let mut nonce = None;
+    let mut ciphertext = None;
+    for field in fields.split(',') {
+        let mut parts = field.splitn(2, ':');
+        let (name, encoded) = match (parts.next(), parts.next()) {
+            (Some(name), Some(encoded)) => (name, encoded),
+            _ => return None,
+        };
+        let bytes = BASE64.decode(encoded).ok()?;
+        match name {
+            "salt" => salt = Some(bytes),
+            "iv" => nonce = Some(bytes),
+            "data" => ciphertext = Some(bytes),
+            _ => {}
+        }
+    }
+    Some(EncryptedValue {
+        salt: salt?,
+        nonce: nonce?,
+        ciphertext: ciphertext?,
+    })
+}
+
+fn random_bytes(length: usize) -> Vec<u8> {
+    let mut bytes = vec![0u8; length];
+    OsRng.fill_bytes(&mut bytes);
+    bytes
+}
+
+// the passphrase in the key file, from the schema or `NEST_KEY_FILE`
+fn read_key(key_file: &Option<PathBuf>) -> Result<String, Error> {
+    let key_file = match key_file {
+        Some(key_file) => key_file.clone(),
+        None => env::var_os(KEY_FILE_ENV)
+            .map(PathBuf::from)
+            .context(error::MissingKeyFile)?,
+    };
+    let key = read_to_string(&key_file).context(error::KeyFile { path: &key_file })?;
+    Ok(key.trim().to_owned())
+}
@@ -22,7 +22,7 @@ impl FileSource for Hjson {
     }
 
     fn deserialize(&self, string: &str) -> Result<Self::Value, Self::DeError> {
-        hjson::from_str(&string)
+        hjson::from_str(string)
     }
 
     fn serialize(&self, value: &Self::Value) -> Result<String, Self::SerError> {
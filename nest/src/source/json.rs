@@ -22,7 +22,7 @@ impl FileSource for Json {
     }
 
     fn deserialize(&self, string: &str) -> Result<Self::Value, Self::DeError> {
-        json::from_str(&string)
+        json::from_str(string)
     }
 
     fn serialize(&self, value: &Self::Value) -> Result<String, Self::SerError> {
@@ -1,21 +1,24 @@
 use std::convert::{TryFrom, TryInto};
 use std::fmt;
-use std::fs::read_to_string;
-use std::io::{self, Write};
+use std::io;
 use std::path::{Path, PathBuf};
 
-use atomicwrites::{AtomicFile, OverwriteBehavior};
 use objekt;
 use snafu::ResultExt;
 
+use crate::backend::Backend;
 use crate::error::{self, Error};
 use crate::value::Value;
 
+#[cfg(feature = "encryption")]
+pub(crate) mod encrypted;
 mod hjson;
 mod json;
 mod toml;
 mod yaml;
 
+#[cfg(feature = "encryption")]
+pub use self::encrypted::{EncryptMode, Encrypted};
 pub use self::hjson::Hjson;
 pub use self::json::Json;
 pub use self::toml::Toml;
@@ -32,8 +35,23 @@ lazy_static! {
 
 pub trait Source: Send + Sync + objekt::Clone + fmt::Debug {
     fn id(&self) -> String;
-    fn read(&self, path: PathBuf) -> Result<Value, Error>;
-    fn write(&self, path: PathBuf, value: &Value) -> Result<(), Error>;
+    fn extensions(&self) -> Vec<String>;
+    /// The file path of the source (file) at `path` (without extension) in the `backend`.
+    fn file_path(&self, backend: &dyn Backend, path: PathBuf) -> PathBuf;
+    /// Parse the contents of the source (file) at `path` as a value.
+    fn parse(&self, path: &Path, string: &str) -> Result<Value, Error>;
+    /// Format a value as the contents of the source (file) at `path`.
+    fn stringify(&self, path: &Path, value: &Value) -> Result<String, Error>;
+    fn read(&self, backend: &dyn Backend, path: PathBuf) -> Result<Value, Error>;
+    fn write(&self, backend: &dyn Backend, path: PathBuf, value: &Value) -> Result<(), Error>;
+    /// Any keys besides `"$format"` in the schema leaf for this source, such as `"$encrypt"`.
+    fn options(&self) -> Vec<(String, Value)> {
+        Vec::new()
+    }
+    /// For an encrypted source, the same source encrypted with the key at `key_file`.
+    fn rekey(&self, _key_file: &Path) -> Option<Box<dyn Source>> {
+        None
+    }
 }
 
 objekt::clone_trait_object!(Source);
@@ -43,7 +61,12 @@ pub trait FileSource: Send + Sync + objekt::Clone + fmt::Debug {
     type SerError: 'static + std::error::Error;
     type DeError: 'static + std::error::Error;
 
+    /// The preferred file extension, used when creating new files.
     fn extension(&self) -> String;
+    /// All file extensions accepted when reading existing files, in order of precedence.
+    fn extensions(&self) -> Vec<String> {
+        vec![self.extension()]
+    }
     fn deserialize(&self, string: &str) -> Result<Self::Value, Self::DeError>;
     fn serialize(&self, value: &Self::Value) -> Result<String, Self::SerError>;
 }
@@ -58,17 +81,22 @@ where
         self.extension()
     }
 
-    fn read(&self, path: PathBuf) -> Result<Value, Error> {
-        let file_path = path.with_extension(self.extension());
-        let file_string =
-            read_file(&file_path).context(error::ReadSource { path: path.clone() })?;
+    fn extensions(&self) -> Vec<String> {
+        FileSource::extensions(self)
+    }
+
+    fn file_path(&self, backend: &dyn Backend, path: PathBuf) -> PathBuf {
+        file_path(self, backend, &path)
+    }
+
+    fn parse(&self, path: &Path, string: &str) -> Result<Value, Error> {
         let file_value = self
-            .deserialize(&file_string)
+            .deserialize(string)
             .map_err(|err| -> Box<dyn std::error::Error> { Box::new(err) })
             .context(error::Deserialize {
                 kind: self.extension(),
-                path: path.clone(),
-                string: file_string.clone(),
+                path,
+                string,
             })?;
         let value: Value = file_value
             .clone()
@@ -76,21 +104,20 @@ where
             .map_err(|err| -> Box<dyn std::error::Error> { Box::new(err) })
             .context(error::IntoValue {
                 kind: self.extension(),
-                path: path.clone(),
+                path,
                 value: Box::new(file_value.clone()) as Box<dyn fmt::Debug>,
             })?;
         Ok(value)
     }
 
-    fn write(&self, path: PathBuf, value: &Value) -> Result<(), Error> {
-        let file_path = path.with_extension(self.extension());
+    fn stringify(&self, path: &Path, value: &Value) -> Result<String, Error> {
         let file_value = value
             .clone()
             .try_into()
             .map_err(|err| -> Box<dyn std::error::Error> { Box::new(err) })
             .context(error::FromValue {
                 kind: self.extension(),
-                path: path.clone(),
+                path,
                 value: value.clone(),
             })?;
         let file_string = self
@@ -98,24 +125,100 @@ where
             .map_err(|err| -> Box<dyn std::error::Error> { Box::new(err) })
             .context(error::Serialize {
                 kind: self.extension(),
-                path: path.clone(),
+                path,
                 value: value.clone(),
             })?;
-        write_file(&file_path, file_string).context(error::WriteSource { path: path.clone() })?;
+        Ok(file_string)
+    }
+
+    fn read(&self, backend: &dyn Backend, path: PathBuf) -> Result<Value, Error> {
+        let file_path = file_path(self, backend, &path);
+        let file_string =
+            read_file(backend, &file_path).context(error::ReadSource { path: path.clone() })?;
+        self.parse(&path, &file_string)
+    }
+
+    fn write(&self, backend: &dyn Backend, path: PathBuf, value: &Value) -> Result<(), Error> {
+        let file_path = file_path(self, backend, &path);
+        let file_string = self.stringify(&path, value)?;
+        write_file(backend, &file_path, file_string)
+            .context(error::WriteSource { path: path.clone() })?;
         Ok(())
     }
 }
 
 /* utils */
-fn read_file(path: &Path) -> Result<String, io::Error> {
-    read_to_string(path)
+// use whichever accepted extension already exists, otherwise the preferred extension
+fn file_path<A>(source: &A, backend: &dyn Backend, path: &Path) -> PathBuf
+where
+    A: FileSource,
+{
+    find_file(backend, path, &FileSource::extensions(source))
+        .unwrap_or_else(|| append_extension(path, &source.extension()))
 }
 
-fn write_file(path: &Path, data: String) -> Result<(), io::Error> {
-    let atomic_file = AtomicFile::new(path, OverwriteBehavior::AllowOverwrite);
-    match atomic_file.write(|file| file.write_all(data.as_bytes())) {
-        Ok(()) => Ok(()),
-        Err(atomicwrites::Error::Internal(io_error)) => Err(io_error),
-        Err(atomicwrites::Error::User(io_error)) => Err(io_error),
-    }
+// find an existing file at `path` with any of the given `extensions`
+pub(crate) fn find_file(
+    backend: &dyn Backend,
+    path: &Path,
+    extensions: &[String],
+) -> Option<PathBuf> {
+    extensions
+        .iter()
+        .map(|extension| append_extension(path, extension))
+        .find(|file_path| backend.is_file(file_path))
+}
+
+// strip any of the given `extensions` from `file_name`, returning `None` if none match
+pub(crate) fn strip_extension(file_name: &str, extensions: &[String]) -> Option<String> {
+    extensions.iter().find_map(|extension| {
+        let suffix = format!(".{}", extension);
+        if file_name.len() > suffix.len() && file_name.ends_with(&suffix) {
+            Some(file_name[..file_name.len() - suffix.len()].to_owned())
+        } else {
+            None
+        }
+    })
+}
+
+// list the visible entries of a directory as (file name, path), sorted by file name
+pub(crate) fn list_directory(
+    backend: &dyn Backend,
+    directory_path: &Path,
+) -> Result<Vec<(String, PathBuf)>, Error> {
+    let mut file_names = backend.list(directory_path).context(error::ReadDirectory {
+        path: directory_path,
+    })?;
+    file_names.sort();
+
+    Ok(file_names
+        .into_iter()
+        // ignore hidden files, such as `.nest.json`
+        .filter(|file_name| !file_name.starts_with('.'))
+        .map(|file_name| {
+            let entry_path = directory_path.join(&file_name);
+            (file_name, entry_path)
+        })
+        .collect())
+}
+
+// unlike `Path::with_extension`, keep anything after a dot in the file name (e.g. `v1.2`)
+pub(crate) fn append_extension(path: &Path, extension: &str) -> PathBuf {
+    let mut file_path = path.as_os_str().to_owned();
+    file_path.push(".");
+    file_path.push(extension);
+    file_path.into()
+}
+
+pub(crate) fn read_file(backend: &dyn Backend, path: &Path) -> Result<String, io::Error> {
+    let bytes = backend.read(path)?;
+    String::from_utf8(bytes).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+pub(crate) fn write_file(
+    backend: &dyn Backend,
+    path: &Path,
+    data: String,
+) -> Result<(), io::Error> {
+    backend.write(path, data.as_bytes())
 }
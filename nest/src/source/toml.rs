@@ -22,7 +22,7 @@ impl FileSource for Toml {
     }
 
     fn deserialize(&self, string: &str) -> Result<Self::Value, Self::DeError> {
-        toml::from_str(&string)
+        toml::from_str(string)
     }
 
     fn serialize(&self, value: &Self::Value) -> Result<String, Self::SerError> {
@@ -20,13 +20,20 @@ impl FileSource for Yaml {
         "yaml".into()
     }
 
+    fn extensions(&self) -> Vec<String> {
+        vec!["yaml".into(), "yml".into()]
+    }
+
     fn deserialize(&self, string: &str) -> Result<Self::Value, Self::DeError> {
-        yaml::from_str(&string)
+        yaml::from_str(string)
     }
 
     fn serialize(&self, value: &Self::Value) -> Result<String, Self::SerError> {
         let mut string = yaml::to_string(&value)?;
-        string.push('\n');
+        // newer versions of serde_yaml end with a newline already
+        if !string.ends_with('\n') {
+            string.push('\n');
+        }
         Ok(string)
     }
 }
@@ -0,0 +1,93 @@
+use std::convert::TryFrom;
+use std::path;
+
+use rusqlite::{params, Connection};
+use snafu::ResultExt;
+
+use crate::error::{self, BoxError, Result};
+use crate::path::Path;
+use crate::value::Value;
+
+fn sqlite_error(err: rusqlite::Error) -> BoxError {
+    Box::new(err)
+}
+
+// replace the `nest` table in the database at `db_path` with a row for each (path, value), where
+// the path is a json array of keys, since keys may contain `/`
+pub(crate) fn export(db_path: &path::Path, pairs: Vec<(Path, Value)>) -> Result<()> {
+    let rows = pairs
+        .into_iter()
+        .map(|(path, value)| {
+            let json_value = serde_json::Value::try_from(value.clone())
+                .map_err(|err| -> BoxError { Box::new(err) })
+                .context(error::FromValue {
+                    kind: "json",
+                    path: db_path,
+                    value,
+                })?;
+            let keys: Vec<&str> = path.iter().map(String::as_str).collect();
+            Ok((
+                serde_json::Value::from(keys).to_string(),
+                json_value.to_string(),
+            ))
+        })
+        .collect::<Result<Vec<(String, String)>>>()?;
+
+    let mut connection = Connection::open(db_path)
+        .map_err(sqlite_error)
+        .context(error::Sqlite { path: db_path })?;
+    let transaction = connection
+        .transaction()
+        .map_err(sqlite_error)
+        .context(error::Sqlite { path: db_path })?;
+    transaction
+        .execute_batch(
+            "DROP TABLE IF EXISTS nest;
+             CREATE TABLE nest (path JSON PRIMARY KEY, value JSON NOT NULL);",
+        )
+        .and_then(|()| {
+            let mut statement =
+                transaction.prepare("INSERT INTO nest (path, value) VALUES (?1, ?2)")?;
+            rows.iter()
+                .try_for_each(|(path, value)| statement.execute(params![path, value]).map(|_| ()))
+        })
+        .and_then(|()| transaction.commit())
+        .map_err(sqlite_error)
+        .context(error::Sqlite { path: db_path })
+}
+
+// every (path, value) row in the `nest` table of the database at `db_path`, in insert order
+pub(crate) fn import(db_path: &path::Path) -> Result<Vec<(Path, Value)>> {
+    let connection = Connection::open(db_path)
+        .map_err(sqlite_error)
+        .context(error::Sqlite { path: db_path })?;
+    let rows = connection
+        .prepare("SELECT path, value FROM nest ORDER BY rowid")
+        .and_then(|mut statement| {
+            statement
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<rusqlite::Result<Vec<(String, String)>>>()
+        })
+        .map_err(sqlite_error)
+        .context(error::Sqlite { path: db_path })?;
+
+    rows.into_iter()
+        .map(|(path_string, string)| {
+            let keys: Vec<String> = serde_json::from_str(&path_string)
+                .map_err(|err| -> BoxError { Box::new(err) })
+                .context(error::Deserialize {
+                    kind: "json",
+                    path: db_path,
+                    string: path_string.clone(),
+                })?;
+            let json_value: serde_json::Value = serde_json::from_str(&string)
+                .map_err(|err| -> BoxError { Box::new(err) })
+                .context(error::Deserialize {
+                    kind: "json",
+                    path: db_path,
+                    string: string.clone(),
+                })?;
+            Ok(((&keys).into(), json_value.into()))
+        })
+        .collect()
+}
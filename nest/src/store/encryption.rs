@@ -0,0 +1,123 @@
+use std::path;
+
+use log::info;
+
+use super::{source_files_in_schema, Store};
+use crate::backend::Backend;
+use crate::error::Result;
+use crate::path::Path;
+use crate::schema::{Schema, KEY_FILE_KEY};
+use crate::source::{strip_extension, Source};
+
+impl Store {
+    /// Re-encrypt every encrypted source (file) with the key at `key_file`, returning the schema
+    /// with every `"$key"` pointing to `key_file`.
+    ///
+    /// Every source (file) is decrypted and encrypted again before any are replaced. Afterwards,
+    /// the returned schema (or `NEST_KEY_FILE`, for sources without a `"$key"`) should be used.
+    pub fn rekey<A>(&self, key_file: A) -> Result<Schema>
+    where
+        A: AsRef<path::Path>,
+    {
+        let key_file = key_file.as_ref();
+        info!("nest::Store#rekey({:?})", key_file);
+
+        let mut files = Vec::new();
+        let next_schema = rekey_in_schema(
+            &*self.backend,
+            &self.schema,
+            &self.root,
+            Path::default(),
+            key_file,
+            &mut files,
+        )?;
+        self.replace_files(files)?;
+        Ok(next_schema)
+    }
+}
+
+// the schema with every pinned key file replaced by `key_file`, adding the contents of each
+// encrypted source (file) with the new key to `files`
+fn rekey_in_schema(
+    backend: &dyn Backend,
+    schema: &Schema,
+    root: &path::Path,
+    path: Path,
+    key_file: &path::Path,
+    files: &mut Vec<(path::PathBuf, Vec<u8>)>,
+) -> Result<Schema> {
+    match schema {
+        Schema::Directory(map) => map
+            .iter()
+            .map(|(key, nested_schema)| -> Result<(String, Schema)> {
+                let next_schema = rekey_in_schema(
+                    backend,
+                    nested_schema,
+                    root,
+                    path.append(key),
+                    key_file,
+                    files,
+                )?;
+                Ok((key.clone(), next_schema))
+            })
+            .collect::<Result<_>>()
+            .map(Schema::Directory),
+        Schema::Default(leaf, default) => {
+            let next_leaf = rekey_in_schema(backend, leaf, root, path, key_file, files)?;
+            Ok(Schema::Default(Box::new(next_leaf), default.clone()))
+        }
+        // linked sources (files) are rekeyed where they are
+        Schema::Link(_) => Ok(schema.clone()),
+        Schema::Source(source) | Schema::Tree(source) | Schema::Split(source) => {
+            let next_source = match source.rekey(key_file) {
+                Some(next_source) => next_source,
+                None => return Ok(schema.clone()),
+            };
+            rekey_files(backend, schema, &**source, &*next_source, root, path, files)?;
+
+            // sources (files) without a pinned key file keep using `NEST_KEY_FILE`
+            let is_pinned = source
+                .options()
+                .iter()
+                .any(|(option, _)| option == KEY_FILE_KEY);
+            if !is_pinned {
+                return Ok(schema.clone());
+            }
+            Ok(match schema {
+                Schema::Tree(_) => Schema::Tree(next_source),
+                Schema::Split(_) => Schema::Split(next_source),
+                _ => Schema::Source(next_source),
+            })
+        }
+    }
+}
+
+// add the contents of each existing source (file) of the leaf `schema`, read with `source` and
+// written with `next_source`, to `files`
+fn rekey_files(
+    backend: &dyn Backend,
+    schema: &Schema,
+    source: &dyn Source,
+    next_source: &dyn Source,
+    root: &path::Path,
+    path: Path,
+    files: &mut Vec<(path::PathBuf, Vec<u8>)>,
+) -> Result<()> {
+    let extensions = source.extensions();
+    let depth = path.len();
+    source_files_in_schema(backend, schema, root, path, depth)?
+        .into_iter()
+        .try_for_each(|file_path| -> Result<()> {
+            let file_name = file_path
+                .file_name()
+                .map(|file_name| file_name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            if let Some(key) = strip_extension(&file_name, &extensions) {
+                let source_path = file_path.with_file_name(key);
+                let value = source.read(backend, source_path.clone())?;
+                let file_string = next_source.stringify(&source_path, &value)?;
+                files.push((file_path, file_string.into_bytes()));
+            }
+            Ok(())
+        })
+}
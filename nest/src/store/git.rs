@@ -0,0 +1,80 @@
+use log::info;
+use snafu::ResultExt;
+
+use super::{skip_absent, Store};
+use crate::error::{self, Result};
+use crate::git::{self, Revision};
+use crate::path::Path;
+use crate::snapshot::Snapshot;
+
+impl Store {
+    // write, then commit the sources (files) under `path` from before and after the write
+    pub(super) fn commit_changes<F>(&self, action: &str, path: Path, write: F) -> Result<()>
+    where
+        F: FnOnce() -> Result<()>,
+    {
+        let message = match self.commit_message {
+            Some(ref message) => message
+                .replace("{action}", action)
+                .replace("{path}", &path.to_string()),
+            None => return write(),
+        };
+
+        // a path with no sources (files), such as above a mount, has nothing to commit
+        let mut origins = self.source_files(path.clone()).unwrap_or_default();
+        write()?;
+        origins.extend(self.source_files(path).unwrap_or_default());
+        origins.sort();
+        origins.dedup();
+        if origins.is_empty() {
+            return Ok(());
+        }
+        git::commit(&self.root, &origins, &message)
+    }
+
+    /// Get the past values at the given `path` from the git log, newest first.
+    ///
+    /// Only commits which changed the value are included.
+    pub fn history<A>(&self, path: A) -> Result<Vec<Revision>>
+    where
+        A: Into<Path>,
+    {
+        let path = path.into();
+        info!("nest::Store#history({:?})", path);
+
+        let origin = self.get_origin(path.clone())?;
+        let mut revisions: Vec<Revision> = Vec::new();
+        git::history(&self.root, &origin)?
+            .into_iter()
+            .try_for_each(|commit| -> Result<()> {
+                // read the value as of the commit from a temporary store
+                let directory = tempfile::tempdir().context(error::MakeDirectory {
+                    path: std::env::temp_dir(),
+                })?;
+                let store =
+                    Store::new(directory.path(), self.schema.clone()).with_missing(self.missing);
+                store.restore(&Snapshot::new(path.clone(), commit.files))?;
+                let value = match skip_absent(store.get(path.clone()))? {
+                    Some(value) => value,
+                    None => return Ok(()),
+                };
+
+                let is_changed = match revisions.last() {
+                    Some(revision) => revision.value != value,
+                    None => true,
+                };
+                if is_changed {
+                    revisions.push(Revision {
+                        id: commit.id,
+                        time: commit.time,
+                        message: commit.message,
+                        value,
+                    });
+                }
+                Ok(())
+            })?;
+
+        revisions.reverse();
+        Ok(revisions)
+    }
+}
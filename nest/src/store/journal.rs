@@ -0,0 +1,60 @@
+use std::io;
+
+use log::info;
+
+use super::{skip_absent, Missing, Store};
+use crate::error::Result;
+use crate::journal::{self, JournalEntry};
+use crate::path::Path;
+
+impl Store {
+    // write, then record the change in the journal (if enabled)
+    pub(super) fn record_changes<F>(&self, action: &str, path: Path, write: F) -> Result<()>
+    where
+        F: FnOnce() -> Result<()>,
+    {
+        let (journal_root, journal_path) = match self.journal {
+            Some(ref journal) => journal,
+            None => return self.commit_changes(action, path, write),
+        };
+
+        let old_value = skip_absent(self.get_path(path.clone(), true, Missing::Skip))?;
+        self.commit_changes(action, path.clone(), write)?;
+        let new_value = if action == "delete" {
+            None
+        } else {
+            skip_absent(self.get_path(path.clone(), true, Missing::Skip))?
+        };
+
+        // the path is relative to the journaled store, for a sub-store
+        let path: Vec<&String> = journal_path.iter().chain(path.iter()).collect();
+        let entry = JournalEntry::new(
+            action,
+            (&path).into(),
+            old_value,
+            new_value,
+            self.actor.clone(),
+        );
+        journal::append(&*self.backend, journal_root, &entry)
+    }
+
+    /// Rebuild state by applying every change in a `journal` (such as `.nest/journal`), in order.
+    ///
+    /// Replayed changes are not journaled (or committed) again.
+    pub fn replay<R>(&self, journal: R) -> Result<()>
+    where
+        R: io::Read,
+    {
+        info!("nest::Store#replay()");
+
+        journal::read(journal)?
+            .into_iter()
+            .try_for_each(|entry| match entry.new {
+                Some(ref new_value) => {
+                    let value = self.get_existing(entry.path.clone())?;
+                    self.write_changes(entry.path, &value, new_value)
+                }
+                None => self.delete_path(entry.path),
+            })
+    }
+}
@@ -0,0 +1,1915 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::io;
+use std::path;
+use std::sync::Arc;
+
+use indexmap::IndexMap;
+use log::{debug, info};
+use snafu::{ensure, OptionExt, ResultExt};
+
+#[cfg(feature = "archive")]
+use crate::backend::{write_archive, ArchiveFormat};
+use crate::backend::{Backend, InMemory, LocalFs};
+use crate::diff::{diff, Change};
+use crate::error::{self, Error, Result};
+use crate::flatten::{flatten, unflatten};
+use crate::interpolate::{interpolate, Reference};
+use crate::middleware::StoreMiddleware;
+use crate::patch::{self, PatchOperation};
+use crate::path::Path;
+use crate::schema::Schema;
+use crate::snapshot::Snapshot;
+use crate::source::{find_file, list_directory, strip_extension, Source};
+#[cfg(feature = "sqlite")]
+use crate::sqlite;
+use crate::value::Value;
+
+#[cfg(feature = "encryption")]
+mod encryption;
+#[cfg(feature = "git")]
+mod git;
+mod journal;
+mod mount;
+
+/// The entry point for a Nest data store.
+///
+/// Stores start with a *root* path and use a *schema* to map the topology of your data structures
+/// with the filesystem (files and directories).
+///
+/// # Example
+///
+/// Given a filesystem setup like:
+///
+/// ```txt
+/// $ tree /home/dinosaur/example
+/// /home/dinosaur/example
+/// └── hello
+///     └── world.json
+///
+/// $ cat /home/dinosaur/example/hello/world.json
+/// {
+///   "nest": "🐣"
+/// }
+/// ```
+///
+/// We can create a simple Nest data store with:
+///
+/// ```rust, no_run
+/// use std::convert::TryInto;
+/// use serde_json::json;
+/// use nest::{Error, Store, Value};
+///
+/// let root = "/home/dinosaur/example";
+/// let schema = json!({
+///     "hello": {
+///         "world": "json"
+///     }
+/// }).try_into()?;
+/// let store = Store::new(root, schema);
+/// # Ok::<(), Error>(())
+/// ```
+///
+/// Now we can use this store to get and set values.
+///
+/// ```rust, no_run
+/// # use std::convert::TryInto;
+/// # use nest::{Store, Error, Value};
+/// # let store = Store::new("./", serde_json::json!({}).try_into()?);
+/// let value = store.get(&["hello", "world", "nest"])?;
+/// assert_eq!(value, Value::String("🐣".into()));
+///
+/// let next_value = Value::String("🐥".into());
+/// store.set(&["hello", "world", "nest"], &next_value)?;
+/// # Ok::<(), Error>(())
+/// ```
+///
+/// If we mostly care about data starting with a given path within the Nest, we can create a
+/// sub-Store that contains our path as a new root.
+///
+/// ```rust, no_run
+/// # use std::convert::TryInto;
+/// # use nest::{Store, Error, Value};
+/// # let store = Store::new("./", serde_json::json!({}).try_into()?);
+/// let sub = store.sub(&["hello", "world"])?;
+///
+/// let value = store.get(&["nest"])?;
+/// assert_eq!(value, Value::String("🐥".into()));
+///
+/// let next_value = Value::String("🐔".into());
+/// store.set(&["nest"], &next_value)?;
+/// # Ok::<(), Error>(())
+/// ```
+#[derive(Clone)]
+pub struct Store {
+    root: path::PathBuf,
+    schema: Schema,
+    backend: Arc<dyn Backend>,
+    missing: Missing,
+    mode: SetMode,
+    // the root of the journaled store, and the path of this store within it
+    journal: Option<(path::PathBuf, Path)>,
+    actor: Option<String>,
+    middleware: Vec<Arc<dyn StoreMiddleware>>,
+    interpolate: bool,
+    // the top-level store that `${ref:…}` paths are from, and the path of this store within it
+    references: Option<(Arc<Store>, Path)>,
+    mounts: Vec<(Path, Arc<Store>)>,
+    #[cfg(feature = "git")]
+    commit_message: Option<String>,
+}
+
+/// What [`Store::get`](struct.Store.html#method.get) does when a source (file) or tree directory
+/// does not exist yet.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Missing {
+    /// Fail with the `Error::ReadSource` (or `Error::ReadDirectory`) error, the default.
+    Error,
+    /// Leave the key out of the parent object.
+    Skip,
+    /// Use `Value::Null`.
+    Null,
+    /// Use an empty `Value::Object`.
+    Empty,
+    /// Use the `default` declared in the schema, failing as `Missing::Error` if there is none.
+    Default,
+}
+
+/// How [`Store::set`](struct.Store.html#method.set) treats existing keys missing from an object
+/// value set on a directory.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SetMode {
+    /// Keep sources (files) for keys missing from the value, the default. Keys in the value which
+    /// are not in the schema are ignored.
+    Merge,
+    /// Remove sources (files) for keys missing from the value. Keys in the value which are not in
+    /// the schema are an error, and nothing is written.
+    Replace,
+}
+
+impl Store {
+    /// Create a `Store` from `root` path and `schema` mapping.
+    pub fn new<A>(root: A, schema: Schema) -> Self
+    where
+        A: Into<path::PathBuf>,
+    {
+        let root = root.into();
+        info!("nest::Store::new({:?}, {:?})", root, schema);
+
+        // TODO validate schema
+        Store {
+            root,
+            schema,
+            backend: Arc::new(LocalFs),
+            missing: Missing::Error,
+            mode: SetMode::Merge,
+            journal: None,
+            actor: None,
+            middleware: Vec::new(),
+            interpolate: false,
+            references: None,
+            mounts: Vec::new(),
+            #[cfg(feature = "git")]
+            commit_message: None,
+        }
+    }
+
+    /// Create a `Store` held in memory with the `schema` mapping, such as for tests.
+    ///
+    /// Values are read and written through the same sources (files) as on disk, and the
+    /// contents can be checked with [`dump`](#method.dump). Since the root is empty, a schema
+    /// which is only a source (file) can't be set, and fails with `Error::SourceFileName`.
+    ///
+    /// ```rust
+    /// use std::convert::TryInto;
+    /// use std::path::Path;
+    /// use serde_json::json;
+    /// use nest::{Error, Store};
+    ///
+    /// let store = Store::in_memory(json!({ "hello": { "world": "json" } }).try_into()?);
+    /// store.set(&["hello", "world", "nest"], &json!("🐣").into())?;
+    ///
+    /// let files = store.dump()?;
+    /// assert_eq!(files[Path::new("hello/world.json")], "{\n  \"nest\": \"🐣\"\n}\n");
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn in_memory(schema: Schema) -> Self {
+        Store::new("", schema).with_backend(InMemory::new())
+    }
+
+    /// Keep the sources (files) and directories in the given `Backend`, instead of the local
+    /// filesystem.
+    pub fn with_backend<A>(mut self, backend: A) -> Self
+    where
+        A: Backend + 'static,
+    {
+        self.backend = Arc::new(backend);
+        self
+    }
+
+    /// Use the given `Missing` policy when getting values from sources (files) that don't exist.
+    pub fn with_missing(mut self, missing: Missing) -> Self {
+        self.missing = missing;
+        self
+    }
+
+    /// Use the given `SetMode` when setting object values on directories.
+    pub fn with_set_mode(mut self, mode: SetMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Append every successful change to the journal, as JSON Lines at `.nest/journal` within
+    /// the store root.
+    ///
+    /// Each entry has the `time` (milliseconds since the Unix epoch), `action`, `path`, `old`
+    /// and `new` values, and `actor` (if any).
+    pub fn with_journal(mut self) -> Self {
+        self.journal = Some((self.root.clone(), Path::default()));
+        self
+    }
+
+    /// Record `actor` (such as a user name) as who made each change in the journal.
+    pub fn with_actor<A>(mut self, actor: A) -> Self
+    where
+        A: Into<String>,
+    {
+        self.actor = Some(actor.into());
+        self
+    }
+
+    /// Add `middleware` with hooks around reads and writes.
+    ///
+    /// `before_set` and `after_set` hooks are called in the order middleware was added, and
+    /// `after_get` hooks in reverse order, so each middleware can undo its own transform.
+    pub fn with_middleware<A>(mut self, middleware: A) -> Self
+    where
+        A: StoreMiddleware + 'static,
+    {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    /// Expand references within string values on [`get`](#method.get).
+    ///
+    /// `"${env:DATABASE_URL}"` is replaced with the environment variable, and
+    /// `"${ref:hello/world/nest}"` with the value at the path (relative to the store root, also
+    /// within a [`sub`](#method.sub)-`Store`). A
+    /// string which is only a reference is replaced with the referenced value as is, otherwise
+    /// values are spliced into the string (as JSON, unless a string). Write `$${` for a literal
+    /// `${`.
+    pub fn with_interpolation(mut self) -> Self {
+        self.interpolate = true;
+        self
+    }
+
+    /// Serve the values at `path` from another `store`, with its own root and schema.
+    ///
+    /// Getting (or setting or deleting) a value above `path` includes the values in the mounted
+    /// store. The middleware, interpolation and journal of this store apply to mounted values as
+    /// to any other, but those of the mounted store (and its git commits) are not used, and git
+    /// commits of this store don't include the sources (files) of the mounted store.
+    ///
+    /// History and rekeying only cover the sources (files) of this store, and snapshots (or
+    /// restores) including a mounted store fail with `Error::SnapshotMount`.
+    pub fn mount<A>(mut self, path: A, store: Store) -> Self
+    where
+        A: Into<Path>,
+    {
+        self.mounts.push((path.into(), Arc::new(store)));
+        self
+    }
+
+    /// Commit the sources (files) touched by every successful change to the git repository
+    /// containing the store root.
+    ///
+    /// In the commit `message`, `{action}` is replaced with the change (such as `set` or
+    /// `delete`) and `{path}` with the path changed.
+    #[cfg(feature = "git")]
+    pub fn with_git_commits<A>(mut self, message: A) -> Self
+    where
+        A: Into<String>,
+    {
+        self.commit_message = Some(message.into());
+        self
+    }
+
+    /// Get the `Value` at the given `path`.
+    ///
+    /// Values are deep merged over any `default` declared in the schema, and references are
+    /// expanded if [`with_interpolation`](#method.with_interpolation).
+    pub fn get<A>(&self, path: A) -> Result<Value>
+    where
+        A: Into<Path>,
+    {
+        let path = path.into();
+        info!("nest::Store#get({:?})", path);
+
+        self.get_with_missing(path, self.missing)
+    }
+
+    // get the value at `path` as `get` does, with the given `missing` policy
+    pub(crate) fn get_with_missing(&self, path: Path, missing: Missing) -> Result<Value> {
+        let mut value = self.get_path(path.clone(), false, missing)?;
+        if self.interpolate {
+            value = match self.references {
+                // references are from the top-level store, however this store was reached
+                Some(_) => {
+                    let (store, store_path) = self.references_at(&path);
+                    store.interpolate(&store_path, value, &mut vec![store_path.clone()])?
+                }
+                None => self.interpolate(&path, value, &mut vec![path.clone()])?,
+            };
+        }
+        self.after_get(&path, value)
+    }
+
+    /// Get the `Value` at the given `path`, as stored in the sources (files), without defaults,
+    /// interpolation or middleware.
+    pub fn get_raw<A>(&self, path: A) -> Result<Value>
+    where
+        A: Into<Path>,
+    {
+        let path = path.into();
+        info!("nest::Store#get_raw({:?})", path);
+
+        self.get_path(path, true, self.missing)
+    }
+
+    // whether a (raw) value is stored at `path`, skipping missing sources; any error other than a
+    // missing schema, value or source is returned
+    pub(crate) fn has_value(&self, path: Path) -> Result<bool> {
+        Ok(
+            match skip_absent(self.get_path(path, true, Missing::Skip))? {
+                Some(Value::Object(map)) => !map.is_empty(),
+                Some(_) => true,
+                None => false,
+            },
+        )
+    }
+
+    fn get_path(&self, path: Path, raw: bool, missing: Missing) -> Result<Value> {
+        self.get_linked_path(path, raw, missing, &mut Vec::new())
+    }
+
+    // `paths` are the (linked) paths being got, to catch links back into them
+    fn get_linked_path(
+        &self,
+        path: Path,
+        raw: bool,
+        missing: Missing,
+        paths: &mut Vec<Path>,
+    ) -> Result<Value> {
+        let path = self.resolve_links(path)?;
+        if let Some((store, mounted_path)) = self.find_mount(&path) {
+            return store.get_path(mounted_path, raw, missing);
+        }
+        ensure!(!paths.contains(&path), error::LinkCycle { path });
+
+        let mounts = self.mounts_under(&path);
+        let mut value = match traverse_schema(path.clone(), &self.schema) {
+            Some((extra_path, schema)) => {
+                debug!("extra_path: {:?}", extra_path);
+
+                let depth = path.len() - extra_path.len();
+                let mut value = get_in_schema(
+                    &*self.backend,
+                    schema,
+                    &self.root,
+                    path.clone(),
+                    depth,
+                    missing,
+                    raw,
+                )?
+                .context(error::GetValue { path: path.clone() })?;
+
+                // fill in the values of any links under the path
+                paths.push(path);
+                for (link_path, linked_path) in links_in_schema(schema, Path::default()) {
+                    let linked_value = self.get_linked_path(linked_path, raw, missing, paths);
+                    if let Some(linked_value) = skip_missing(linked_value, missing)? {
+                        value = set_in_value(value, link_path, linked_value)?;
+                    }
+                }
+                paths.pop();
+                value
+            }
+            // a path above a mount may only exist in the mounted stores
+            None if !mounts.is_empty() => Value::Object(IndexMap::new()),
+            None => return error::GetSchema { path }.fail(),
+        };
+
+        // fill in the values of any mounted stores under the path
+        for (mount_path, store) in mounts {
+            let mounted_value = store.get_path(Path::default(), raw, missing);
+            if let Some(mounted_value) = skip_missing(mounted_value, missing)? {
+                value = set_in_value(value, mount_path, mounted_value)?;
+            }
+        }
+
+        Ok(value)
+    }
+
+    // `path` with any link along it replaced by the linked path
+    fn resolve_links(&self, path: Path) -> Result<Path> {
+        let mut path = path;
+        let mut link_paths = Vec::new();
+        loop {
+            let (extra_path, linked_path) = match traverse_schema(path.clone(), &self.schema) {
+                Some((extra_path, Schema::Link(linked_path))) => (extra_path, linked_path),
+                _ => return Ok(path),
+            };
+            let link_path = path.take(path.len() - extra_path.len());
+            ensure!(
+                !link_paths.contains(&link_path),
+                error::LinkCycle { path: link_path }
+            );
+            link_paths.push(link_path);
+            path = extra_path
+                .iter()
+                .fold(linked_path.clone(), |path, key| path.append(key));
+        }
+    }
+
+    /// Set the `Value` at the given `path`.
+    pub fn set<A>(&self, path: A, value: &Value) -> Result<()>
+    where
+        A: Into<Path>,
+    {
+        let path = path.into();
+        info!("nest::Store#set({:?}), {:?}", path, value);
+
+        let value = self.before_set(&path, value.clone())?;
+        self.record_changes("set", path.clone(), || self.set_path(path.clone(), &value))?;
+        self.after_set(&path, &value)
+    }
+
+    // set `value` at `path` as stored, without middleware, as for values from `get_raw`
+    fn set_raw(&self, path: Path, value: &Value) -> Result<()> {
+        self.record_changes("set", path.clone(), || self.set_path(path.clone(), value))
+    }
+
+    fn set_path(&self, path: Path, value: &Value) -> Result<()> {
+        let path = self.resolve_links(path)?;
+        if let Some((store, mounted_path)) = self.find_mount(&path) {
+            return store.set_path(mounted_path, value);
+        }
+
+        // the values of any mounted stores under the path are set in the mounted stores
+        let mut value = value.clone();
+        let mut mounted_values = Vec::new();
+        for (mount_path, store) in self.mounts_under(&path) {
+            if let Some(mounted_value) = find_in_value(&value, &mount_path).cloned() {
+                value = delete_in_value(value, mount_path);
+                mounted_values.push((store, mounted_value));
+            }
+        }
+        let set_mounted_values = || {
+            mounted_values
+                .iter()
+                .try_for_each(|(store, mounted_value)| {
+                    store.set_path(Path::default(), mounted_value)
+                })
+        };
+
+        let (extra_path, schema) = match traverse_schema(path.clone(), &self.schema) {
+            Some(found) => found,
+            // a path above a mount may only exist in the mounted stores
+            None if !mounted_values.is_empty() && value == Value::Object(IndexMap::new()) => {
+                return set_mounted_values();
+            }
+            None => return error::GetSchema { path }.fail(),
+        };
+
+        // check the whole value before writing anything
+        if self.mode == SetMode::Replace {
+            if let Some(unknown_path) = find_unknown_key(schema, path.clone(), &value) {
+                return error::SetUnknownKey { path: unknown_path }.fail();
+            }
+        }
+
+        // the values of any links under the path are set at the linked paths
+        let mut linked_values = Vec::new();
+        for (link_path, linked_path) in links_in_schema(schema, Path::default()) {
+            if let Some(linked_value) = find_in_value(&value, &link_path).cloned() {
+                value = delete_in_value(value, link_path);
+                linked_values.push((linked_path, linked_value));
+            }
+        }
+
+        let depth = path.clone().len() - extra_path.len();
+        set_in_schema(
+            &*self.backend,
+            schema,
+            &self.root,
+            path.clone(),
+            &value,
+            depth,
+            self.mode,
+        )?;
+        linked_values
+            .into_iter()
+            .try_for_each(|(linked_path, linked_value)| {
+                self.set_path(linked_path, &linked_value)
+            })?;
+        set_mounted_values()
+    }
+
+    /// Delete the `Value` at the given `path`, removing sources (files) or keys within them.
+    pub fn delete<A>(&self, path: A) -> Result<()>
+    where
+        A: Into<Path>,
+    {
+        let path = path.into();
+        info!("nest::Store#delete({:?})", path);
+
+        self.record_changes("delete", path.clone(), || self.delete_path(path.clone()))
+    }
+
+    fn delete_path(&self, path: Path) -> Result<()> {
+        let path = self.resolve_links(path)?;
+        if let Some((store, mounted_path)) = self.find_mount(&path) {
+            return store.delete_path(mounted_path);
+        }
+
+        let mounts = self.mounts_under(&path);
+        match traverse_schema(path.clone(), &self.schema) {
+            Some((extra_path, schema)) => {
+                let depth = path.len() - extra_path.len();
+                delete_in_schema(&*self.backend, schema, &self.root, path, depth)?;
+            }
+            // a path above a mount may only exist in the mounted stores
+            None if !mounts.is_empty() => {}
+            None => return error::GetSchema { path }.fail(),
+        }
+        mounts
+            .into_iter()
+            .try_for_each(|(_, store)| store.delete_path(Path::default()))
+    }
+
+    /// Get every leaf `Value` under the given `path` with its full path, across sources (files)
+    /// and directories, in order.
+    ///
+    /// Every scalar is a leaf, as are arrays and empty objects, since they have no keys to
+    /// continue the path. Values are as stored, like [`get_raw`](#method.get_raw), so
+    /// [`unflatten`](#method.unflatten) writes them back without defaults or interpolated values.
+    pub fn flatten<A>(&self, path: A) -> Result<Vec<(Path, Value)>>
+    where
+        A: Into<Path>,
+    {
+        let path = path.into();
+        info!("nest::Store#flatten({:?})", path);
+
+        let value = self.get_raw(path.clone())?;
+        Ok(flatten(path, value))
+    }
+
+    /// Set each leaf `Value` at its full path, as returned by [`flatten`](#method.flatten).
+    ///
+    /// Any other values are left as they are. The leaves are merged into the stored value of each
+    /// source (file) they are in, which is then written once, as stored (without middleware).
+    /// Other sources (files) are not written.
+    pub fn unflatten<I>(&self, pairs: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (Path, Value)>,
+    {
+        info!("nest::Store#unflatten()");
+
+        // the leaves of each source (file), by path within the source
+        let mut sources: Vec<(Path, Vec<(Path, Value)>)> = Vec::new();
+        for (path, value) in pairs {
+            let path = self.resolve_links(path)?;
+            let source_path = self.source_path(path.clone())?;
+            let value_path = path.skip(source_path.len());
+            match sources
+                .iter_mut()
+                .find(|(next_path, _)| *next_path == source_path)
+            {
+                Some((_, source_pairs)) => source_pairs.push((value_path, value)),
+                None => sources.push((source_path, vec![(value_path, value)])),
+            }
+        }
+
+        sources.into_iter().try_for_each(|(source_path, pairs)| {
+            let value = self
+                .get_existing(source_path.clone())?
+                .deep_merge(unflatten(pairs));
+            self.set_raw(source_path, &value)
+        })
+    }
+
+    /// Apply a [JSON Merge Patch (RFC 7396)](https://tools.ietf.org/html/rfc7396) to the `Value`
+    /// at the given `path`, where `null` deletes.
+    pub fn merge<A>(&self, path: A, patch: &Value) -> Result<()>
+    where
+        A: Into<Path>,
+    {
+        let path = path.into();
+        info!("nest::Store#merge({:?}, {:?})", path, patch);
+
+        let value = self.get_existing(path.clone())?;
+        let next_value = self.before_set(
+            &path,
+            patch::merge_patch(self.after_get(&path, value.clone())?, patch),
+        )?;
+        self.record_changes("merge", path.clone(), || {
+            self.write_changes(path.clone(), &value, &next_value)
+        })?;
+        self.after_set(&path, &next_value)
+    }
+
+    /// Apply [JSON Patch (RFC 6902)](https://tools.ietf.org/html/rfc6902) operations to the
+    /// `Value` at the given `path`.
+    ///
+    /// If any operation fails, nothing is written.
+    pub fn patch<A>(&self, path: A, operations: &[PatchOperation]) -> Result<()>
+    where
+        A: Into<Path>,
+    {
+        let path = path.into();
+        info!("nest::Store#patch({:?}, {:?})", path, operations);
+
+        let value = self.get_existing(path.clone())?;
+        let next_value = self.before_set(
+            &path,
+            patch::patch(self.after_get(&path, value.clone())?, operations)?,
+        )?;
+        self.record_changes("patch", path.clone(), || {
+            self.write_changes(path.clone(), &value, &next_value)
+        })?;
+        self.after_set(&path, &next_value)
+    }
+
+    // the raw value at `path`, skipping any missing sources (files)
+    fn get_existing(&self, path: Path) -> Result<Value> {
+        match self.get_path(path, true, Missing::Skip) {
+            Err(Error::GetSchema { .. }) | Err(Error::GetValue { .. }) => Ok(Value::Null),
+            result => result,
+        }
+    }
+
+    // set `next_value` at `path`, deleting any keys in `value` which are no longer present
+    fn write_changes(&self, path: Path, value: &Value, next_value: &Value) -> Result<()> {
+        removed_paths(path.clone(), value, next_value)
+            .into_iter()
+            .try_for_each(|removed_path| self.delete_path(removed_path))?;
+        self.set_path(path, next_value)
+    }
+
+    // the top-level store that `${ref:…}` paths are from, with `path` (in this store) from its root
+    fn references_at(&self, path: &Path) -> (Arc<Store>, Path) {
+        match self.references {
+            Some((ref store, ref store_path)) => {
+                let path: Vec<&String> = store_path.iter().chain(path.iter()).collect();
+                (Arc::clone(store), (&path).into())
+            }
+            None => (Arc::new(self.clone()), path.clone()),
+        }
+    }
+
+    // expand references in `value` (at `path`), where `paths` are the paths being expanded
+    fn interpolate(&self, path: &Path, value: Value, paths: &mut Vec<Path>) -> Result<Value> {
+        interpolate(path, value, &mut |value_path, reference| match reference {
+            Reference::Env(name) => {
+                env::var(name)
+                    .ok()
+                    .map(Value::String)
+                    .context(error::UnresolvedReference {
+                        reference: reference.to_string(),
+                        path: value_path.clone(),
+                    })
+            }
+            Reference::Ref(ref_path) => {
+                // a path already being expanded would never finish
+                ensure!(
+                    !paths.contains(ref_path),
+                    error::ReferenceCycle {
+                        reference: reference.to_string(),
+                        path: value_path.clone(),
+                    }
+                );
+                let ref_value = match self.get_path(ref_path.clone(), false, self.missing) {
+                    Ok(ref_value) => ref_value,
+                    Err(err) => {
+                        let is_missing = match err {
+                            Error::GetSchema { .. } | Error::GetValue { .. } => true,
+                            ref err => err.is_not_found(),
+                        };
+                        ensure!(
+                            !is_missing,
+                            error::UnresolvedReference {
+                                reference: reference.to_string(),
+                                path: value_path.clone(),
+                            }
+                        );
+                        return Err(err);
+                    }
+                };
+                paths.push(ref_path.clone());
+                let result = self.interpolate(ref_path, ref_value, paths);
+                paths.pop();
+                result
+            }
+        })
+    }
+
+    fn before_set(&self, path: &Path, value: Value) -> Result<Value> {
+        self.middleware.iter().try_fold(value, |value, middleware| {
+            middleware
+                .before_set(path, value)
+                .context(error::Middleware { path: path.clone() })
+        })
+    }
+
+    fn after_set(&self, path: &Path, value: &Value) -> Result<()> {
+        self.middleware.iter().try_for_each(|middleware| {
+            middleware
+                .after_set(path, value)
+                .context(error::Middleware { path: path.clone() })
+        })
+    }
+
+    fn after_get(&self, path: &Path, value: Value) -> Result<Value> {
+        self.middleware
+            .iter()
+            .rev()
+            .try_fold(value, |value, middleware| {
+                middleware
+                    .after_get(path, value)
+                    .context(error::Middleware { path: path.clone() })
+            })
+    }
+
+    // without the `git` feature, changes are only written
+    #[cfg(not(feature = "git"))]
+    fn commit_changes<F>(&self, _action: &str, _path: Path, write: F) -> Result<()>
+    where
+        F: FnOnce() -> Result<()>,
+    {
+        write()
+    }
+
+    /// Get the contents of every file under the store root (including hidden files, such as the
+    /// journal), by path relative to the root.
+    pub fn dump(&self) -> Result<BTreeMap<path::PathBuf, String>> {
+        info!("nest::Store#dump()");
+
+        let mut files = BTreeMap::new();
+        dump_directory(&*self.backend, &self.root, &self.root, &mut files)?;
+        Ok(files)
+    }
+
+    /// Compare this `Store` with an `other` `Store`, returning the changes from this to other.
+    ///
+    /// Both stores are read as a whole, so they will usually share a schema.
+    pub fn diff(&self, other: &Store) -> Result<Vec<Change>> {
+        info!("nest::Store#diff({:?})", other.root);
+
+        let value = self.get(Path::default())?;
+        let other_value = other.get(Path::default())?;
+        Ok(diff(&value, &other_value))
+    }
+
+    /// Capture the bytes of every source (file) under the given `path`.
+    ///
+    /// If `path` is within a source (file), the whole source (file) is captured. A mounted store
+    /// at or under `path` fails with `Error::SnapshotMount`.
+    pub fn snapshot<A>(&self, path: A) -> Result<Snapshot>
+    where
+        A: Into<Path>,
+    {
+        let path = path.into();
+        info!("nest::Store#snapshot({:?})", path);
+
+        self.ensure_unmounted(&path)?;
+        let mut files = BTreeMap::new();
+        self.source_files(path.clone())?
+            .into_iter()
+            .try_for_each(|file_path| -> Result<()> {
+                let bytes = self
+                    .backend
+                    .read(&file_path)
+                    .context(error::ReadSource { path: &file_path })?;
+                let relative_path = file_path
+                    .strip_prefix(&self.root)
+                    .map_err(|_| Error::Unexpected)?
+                    .to_path_buf();
+                files.insert(relative_path, bytes);
+                Ok(())
+            })?;
+
+        Ok(Snapshot::new(path, files))
+    }
+
+    /// Write every source (file) in this `Store` as an archive at `path` on the local
+    /// filesystem, with the format given by its extension (`.tar`, `.tar.gz` or `.zip`).
+    ///
+    /// The archive can be served with the same schema by the
+    /// [`Archive`](backend/struct.Archive.html) backend. As with `snapshot`, a store with mounted
+    /// stores can't be exported.
+    #[cfg(feature = "archive")]
+    pub fn export_archive<P>(&self, path: P) -> Result<()>
+    where
+        P: AsRef<path::Path>,
+    {
+        let path = path.as_ref();
+        info!("nest::Store#export_archive({:?})", path);
+
+        let format =
+            ArchiveFormat::from_path(path).context(error::UnknownArchiveFormat { path })?;
+        let snapshot = self.snapshot(Path::default())?;
+        let bytes =
+            write_archive(format, snapshot.files()).context(error::WriteArchive { path })?;
+        LocalFs
+            .write(path, &bytes)
+            .context(error::WriteArchive { path })
+    }
+
+    /// Write every value in this `Store`, as stored, into a `nest` table in the SQLite database
+    /// at `path`, replacing any existing table.
+    ///
+    /// The table has a row for each leaf (anything but a non-empty object), with the `path` as a
+    /// JSON array of keys, such as `["hello","world","nest"]`, and the `value` as JSON.
+    #[cfg(feature = "sqlite")]
+    pub fn export_sqlite<P>(&self, path: P) -> Result<()>
+    where
+        P: AsRef<path::Path>,
+    {
+        let path = path.as_ref();
+        info!("nest::Store#export_sqlite({:?})", path);
+
+        let value = self.get_raw(Path::default())?;
+        sqlite::export(path, flatten(Path::default(), value))
+    }
+
+    /// Set the values in this `Store` from the `nest` table in the SQLite database at `path`,
+    /// as written by [`export_sqlite`](#method.export_sqlite).
+    ///
+    /// The whole store is set at once, as stored (without middleware), so the schema decides
+    /// which sources (files) are written.
+    #[cfg(feature = "sqlite")]
+    pub fn import_sqlite<P>(&self, path: P) -> Result<()>
+    where
+        P: AsRef<path::Path>,
+    {
+        let path = path.as_ref();
+        info!("nest::Store#import_sqlite({:?})", path);
+
+        let pairs = sqlite::import(path)?;
+        self.set_raw(Path::default(), &unflatten(pairs))
+    }
+
+    /// Write back the sources (files) captured in a `snapshot`, removing any sources (files)
+    /// under the snapshot path which have been created since.
+    ///
+    /// Every source (file) is written to a temporary file before any are replaced, so a failed
+    /// write replaces nothing. A failure while replacing or removing sources (files) can leave the
+    /// store partly restored, which restoring the same snapshot again completes.
+    pub fn restore(&self, snapshot: &Snapshot) -> Result<()> {
+        info!("nest::Store#restore({:?})", snapshot.path());
+
+        self.ensure_unmounted(snapshot.path())?;
+        let created_paths: Vec<path::PathBuf> = self
+            .source_files(snapshot.path().clone())?
+            .into_iter()
+            .filter(|file_path| match file_path.strip_prefix(&self.root) {
+                Ok(relative_path) => !snapshot.files().contains_key(relative_path),
+                Err(_) => false,
+            })
+            .collect();
+
+        let files = snapshot
+            .files()
+            .iter()
+            .map(|(relative_path, bytes)| (self.root.join(relative_path), bytes.clone()))
+            .collect();
+        self.replace_files(files)?;
+
+        created_paths
+            .iter()
+            .try_for_each(|file_path| -> Result<()> {
+                self.backend
+                    .remove_file(file_path)
+                    .context(error::RemoveSource { path: file_path })
+            })
+    }
+
+    // write every file to a staged file beside it before renaming any into place, so a failed
+    // write replaces nothing
+    fn replace_files(&self, files: Vec<(path::PathBuf, Vec<u8>)>) -> Result<()> {
+        let mut staged_paths = Vec::new();
+        let staged = files
+            .iter()
+            .try_for_each(|(file_path, bytes)| -> Result<()> {
+                let staged_path = staged_path(file_path);
+                if let Some(directory_path) = file_path.parent() {
+                    self.backend
+                        .create_dir(directory_path)
+                        .context(error::MakeDirectory {
+                            path: directory_path,
+                        })?;
+                }
+                self.backend
+                    .write(&staged_path, bytes)
+                    .context(error::WriteSource { path: file_path })?;
+                staged_paths.push((staged_path, file_path));
+                Ok(())
+            });
+        if let Err(err) = staged {
+            staged_paths.iter().for_each(|(staged_path, _)| {
+                let _ = self.backend.remove_file(staged_path);
+            });
+            return Err(err);
+        }
+
+        // any staged files left by a failed rename are removed
+        let mut renamed_count = 0;
+        let renamed = staged_paths
+            .iter()
+            .try_for_each(|(staged_path, file_path)| -> Result<()> {
+                self.backend
+                    .rename(staged_path, file_path)
+                    .context(error::WriteSource { path: *file_path })?;
+                renamed_count += 1;
+                Ok(())
+            });
+        if let Err(err) = renamed {
+            staged_paths[renamed_count..]
+                .iter()
+                .for_each(|(staged_path, _)| {
+                    let _ = self.backend.remove_file(staged_path);
+                });
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    // the existing sources (files) under `path`
+    fn source_files(&self, path: Path) -> Result<Vec<path::PathBuf>> {
+        let path = self.resolve_links(path)?;
+        if self.find_mount(&path).is_some() {
+            return Ok(Vec::new());
+        }
+        let (extra_path, schema) = traverse_schema(path.clone(), &self.schema)
+            .context(error::GetSchema { path: path.clone() })?;
+
+        let depth = path.len() - extra_path.len();
+        source_files_in_schema(&*self.backend, schema, &self.root, path, depth)
+    }
+
+    /// Get the source (file) or directory where the `Value` at the given `path` is stored.
+    pub fn get_origin<A>(&self, path: A) -> Result<path::PathBuf>
+    where
+        A: Into<Path>,
+    {
+        let path = self.resolve_links(path.into())?;
+        info!("nest::Store#get_origin({:?})", path);
+
+        if let Some((store, mounted_path)) = self.find_mount(&path) {
+            return store.get_origin(mounted_path);
+        }
+
+        let (extra_path, schema) = traverse_schema(path.clone(), &self.schema)
+            .context(error::GetSchema { path: path.clone() })?;
+
+        let depth = path.len() - extra_path.len();
+        Ok(origin_in_schema(
+            &*self.backend,
+            schema,
+            &self.root,
+            path,
+            depth,
+        ))
+    }
+
+    // the path of the source (file), or directory, which the value at `path` is stored in
+    fn source_path(&self, path: Path) -> Result<Path> {
+        let path = self.resolve_links(path)?;
+        if let Some((store, mounted_path)) = self.find_mount(&path) {
+            let mount_path = path.take(path.len() - mounted_path.len());
+            let source_path = store.source_path(mounted_path)?;
+            let path: Vec<&String> = mount_path.iter().chain(source_path.iter()).collect();
+            return Ok((&path).into());
+        }
+
+        let (extra_path, schema) = traverse_schema(path.clone(), &self.schema)
+            .context(error::GetSchema { path: path.clone() })?;
+
+        let depth = path.len() - extra_path.len();
+        Ok(path.take(source_depth(
+            &*self.backend,
+            schema,
+            &self.root,
+            &path,
+            depth,
+        )))
+    }
+
+    /// Return a sub-`Store` at the given `path`.
+    ///
+    /// Links within the sub-`Store` must lead to paths within it, otherwise this fails with
+    /// `Error::SubLink`.
+    pub fn sub<A>(&self, path: A) -> Result<Store>
+    where
+        A: Into<Path>,
+    {
+        let path = self.resolve_links(path.into())?;
+        if let Some((store, mounted_path)) = self.find_mount(&path) {
+            let mount_path = path.take(path.len() - mounted_path.len());
+            return self.mounted_store(store, &mount_path).sub(mounted_path);
+        }
+
+        let (extra_path, schema) = traverse_schema(path.clone(), &self.schema)
+            .context(error::GetSchema { path: path.clone() })?;
+
+        let mut depth = path.len() - extra_path.len();
+        if let Schema::Tree(_) = schema {
+            // walk into existing directories within the tree
+            while depth < path.len()
+                && self
+                    .backend
+                    .is_dir(&self.root.join(path.take(depth + 1).to_path()))
+            {
+                depth += 1;
+            }
+        }
+        let nested_path = path.take(depth);
+
+        Ok(Store {
+            schema: sub_links(schema, nested_path.clone(), &nested_path)?,
+            root: self.root.join(nested_path.to_path()),
+            backend: Arc::clone(&self.backend),
+            missing: self.missing,
+            mode: self.mode,
+            journal: self.journal.as_ref().map(|(journal_root, journal_path)| {
+                let journal_path: Vec<&String> =
+                    journal_path.iter().chain(nested_path.iter()).collect();
+                (journal_root.clone(), (&journal_path).into())
+            }),
+            actor: self.actor.clone(),
+            middleware: self.middleware.clone(),
+            interpolate: self.interpolate,
+            references: Some(self.references_at(&nested_path)),
+            mounts: self
+                .mounts_under(&path)
+                .into_iter()
+                .map(|(mount_path, store)| (mount_path, Arc::clone(store)))
+                .collect(),
+            #[cfg(feature = "git")]
+            commit_message: self.commit_message.clone(),
+        })
+    }
+}
+
+fn traverse_schema(path: Path, schema: &Schema) -> Option<(Path, &Schema)> {
+    match schema {
+        Schema::Directory(map) => {
+            if path.is_empty() {
+                return Some((path, schema));
+            }
+            let key = path.first();
+            let next_path = path.rest();
+            match map.get(key) {
+                Some(next_schema) => traverse_schema(next_path, next_schema),
+                None => None,
+            }
+        }
+        leaf => Some((path, leaf)),
+    }
+}
+
+fn origin_in_schema(
+    backend: &dyn Backend,
+    schema: &Schema,
+    root: &path::Path,
+    path: Path,
+    depth: usize,
+) -> path::PathBuf {
+    let leaf_path = root.join(path.take(depth).to_path());
+    match schema {
+        Schema::Directory(_) => leaf_path,
+        Schema::Source(source) => source.file_path(backend, leaf_path),
+        Schema::Tree(source) => {
+            // walk into existing directories within the tree
+            let mut depth = depth;
+            while depth < path.len() && backend.is_dir(&root.join(path.take(depth + 1).to_path())) {
+                depth += 1;
+            }
+            if depth == path.len() {
+                root.join(path.to_path())
+            } else {
+                source.file_path(backend, root.join(path.take(depth + 1).to_path()))
+            }
+        }
+        Schema::Split(source) => {
+            if depth == path.len() {
+                leaf_path
+            } else {
+                source.file_path(backend, leaf_path.join(path.skip(depth).first()))
+            }
+        }
+        Schema::Default(leaf, _) => origin_in_schema(backend, leaf, root, path, depth),
+        // links are resolved before
+        Schema::Link(_) => leaf_path,
+    }
+}
+
+// the length of the path to the source (file), or directory, which the value at `path` is stored
+// in, within the leaf `schema` at `path.take(depth)`
+fn source_depth(
+    backend: &dyn Backend,
+    schema: &Schema,
+    root: &path::Path,
+    path: &Path,
+    depth: usize,
+) -> usize {
+    match schema {
+        Schema::Tree(_) => {
+            // walk into existing directories within the tree
+            let mut depth = depth;
+            while depth < path.len() && backend.is_dir(&root.join(path.take(depth + 1).to_path())) {
+                depth += 1;
+            }
+            (depth + 1).min(path.len())
+        }
+        Schema::Split(_) => (depth + 1).min(path.len()),
+        Schema::Default(leaf, _) => source_depth(backend, leaf, root, path, depth),
+        _ => depth,
+    }
+}
+
+// the schema at `sub_path` with links relative to it, where `path` is the full path of `schema`
+fn sub_links(schema: &Schema, path: Path, sub_path: &Path) -> Result<Schema> {
+    match schema {
+        Schema::Directory(map) => map
+            .iter()
+            .map(|(key, nested_schema)| -> Result<(String, Schema)> {
+                let next_schema = sub_links(nested_schema, path.append(key), sub_path)?;
+                Ok((key.clone(), next_schema))
+            })
+            .collect::<Result<_>>()
+            .map(Schema::Directory),
+        Schema::Link(linked_path) => {
+            ensure!(
+                linked_path.starts_with(sub_path),
+                error::SubLink {
+                    path: path.clone(),
+                    sub_path: sub_path.clone(),
+                }
+            );
+            Ok(Schema::Link(linked_path.skip(sub_path.len())))
+        }
+        _ => Ok(schema.clone()),
+    }
+}
+
+// the paths of links under the schema (relative to `path`), with the linked paths
+fn links_in_schema(schema: &Schema, path: Path) -> Vec<(Path, Path)> {
+    match schema {
+        Schema::Directory(map) => map
+            .iter()
+            .flat_map(|(key, nested_schema)| links_in_schema(nested_schema, path.append(key)))
+            .collect(),
+        Schema::Link(linked_path) => vec![(path, linked_path.clone())],
+        _ => Vec::new(),
+    }
+}
+
+fn source_files_in_schema(
+    backend: &dyn Backend,
+    schema: &Schema,
+    root: &path::Path,
+    path: Path,
+    depth: usize,
+) -> Result<Vec<path::PathBuf>> {
+    let leaf_path = root.join(path.take(depth).to_path());
+    match schema {
+        Schema::Directory(map) => {
+            let mut file_paths = Vec::new();
+            map.iter()
+                .try_for_each(|(key, nested_schema)| -> Result<()> {
+                    let nested_path = path.append(key);
+                    file_paths.extend(source_files_in_schema(
+                        backend,
+                        nested_schema,
+                        root,
+                        nested_path,
+                        depth + 1,
+                    )?);
+                    Ok(())
+                })?;
+            Ok(file_paths)
+        }
+        Schema::Source(source) => Ok(find_file(backend, &leaf_path, &source.extensions())
+            .into_iter()
+            .collect()),
+        Schema::Tree(source) => {
+            // walk into existing directories within the tree
+            let mut depth = depth;
+            while depth < path.len() && backend.is_dir(&root.join(path.take(depth + 1).to_path())) {
+                depth += 1;
+            }
+            if depth == path.len() {
+                tree_files(backend, &**source, &root.join(path.to_path()))
+            } else {
+                let source_path = root.join(path.take(depth + 1).to_path());
+                Ok(find_file(backend, &source_path, &source.extensions())
+                    .into_iter()
+                    .collect())
+            }
+        }
+        Schema::Split(source) => {
+            if depth < path.len() {
+                let source_path = leaf_path.join(path.skip(depth).first());
+                return Ok(find_file(backend, &source_path, &source.extensions())
+                    .into_iter()
+                    .collect());
+            }
+            let entries = match list_directory(backend, &leaf_path) {
+                Err(ref err) if err.is_not_found() => return Ok(Vec::new()),
+                result => result?,
+            };
+            Ok(entries
+                .into_iter()
+                .filter(|(file_name, entry_path)| {
+                    backend.is_file(entry_path)
+                        && strip_extension(file_name, &source.extensions()).is_some()
+                })
+                .map(|(_, entry_path)| entry_path)
+                .collect())
+        }
+        Schema::Default(leaf, _) => source_files_in_schema(backend, leaf, root, path, depth),
+        // linked sources (files) are found where they are
+        Schema::Link(_) => Ok(Vec::new()),
+    }
+}
+
+fn tree_files(
+    backend: &dyn Backend,
+    source: &dyn Source,
+    directory_path: &path::Path,
+) -> Result<Vec<path::PathBuf>> {
+    let entries = match list_directory(backend, directory_path) {
+        Err(ref err) if err.is_not_found() => return Ok(Vec::new()),
+        result => result?,
+    };
+    let extensions = source.extensions();
+    let mut file_paths = Vec::new();
+    entries
+        .into_iter()
+        .try_for_each(|(file_name, entry_path)| -> Result<()> {
+            if backend.is_dir(&entry_path) {
+                file_paths.extend(tree_files(backend, source, &entry_path)?);
+            } else if strip_extension(&file_name, &extensions).is_some() {
+                file_paths.push(entry_path);
+            }
+            Ok(())
+        })?;
+    Ok(file_paths)
+}
+
+fn dump_directory(
+    backend: &dyn Backend,
+    root: &path::Path,
+    directory_path: &path::Path,
+    files: &mut BTreeMap<path::PathBuf, String>,
+) -> Result<()> {
+    let file_names = match backend.list(directory_path) {
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        result => result.context(error::ReadDirectory {
+            path: directory_path,
+        })?,
+    };
+    file_names.into_iter().try_for_each(|file_name| {
+        let entry_path = directory_path.join(file_name);
+        if backend.is_dir(&entry_path) {
+            return dump_directory(backend, root, &entry_path, files);
+        }
+        let bytes = backend
+            .read(&entry_path)
+            .context(error::ReadSource { path: &entry_path })?;
+        let relative_path = entry_path
+            .strip_prefix(root)
+            .map_err(|_| Error::Unexpected)?
+            .to_path_buf();
+        files.insert(relative_path, String::from_utf8_lossy(&bytes).into_owned());
+        Ok(())
+    })
+}
+
+// a hidden sibling of `file_path`, so it is never read as a source (file)
+fn staged_path(file_path: &path::Path) -> path::PathBuf {
+    let file_name = file_path
+        .file_name()
+        .map(|file_name| file_name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    file_path.with_file_name(format!(".{}.nest-restore", file_name))
+}
+
+// a missing value is `None` if the `missing` policy skips it
+fn skip_missing(result: Result<Value>, missing: Missing) -> Result<Option<Value>> {
+    if missing == Missing::Skip {
+        skip_absent(result)
+    } else {
+        result.map(Some)
+    }
+}
+
+// a missing value is `None`, any other error is returned
+pub(crate) fn skip_absent(result: Result<Value>) -> Result<Option<Value>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(Error::GetSchema { .. }) | Err(Error::GetValue { .. }) => Ok(None),
+        Err(err) => {
+            if err.is_not_found() {
+                Ok(None)
+            } else {
+                Err(err)
+            }
+        }
+    }
+}
+
+// returns `None` if the value is missing and should be skipped
+fn get_in_schema(
+    backend: &dyn Backend,
+    schema: &Schema,
+    root: &path::Path,
+    path: Path,
+    depth: usize,
+    missing: Missing,
+    raw: bool,
+) -> Result<Option<Value>> {
+    debug!(
+        "get_in_schema(backend, {:?}, {:?}, {:?}, {:?}, {:?}, {:?})",
+        schema, root, path, depth, missing, raw
+    );
+
+    match schema {
+        // if schema is a directory, it refers to a nested value
+        Schema::Directory(map) => {
+            let mut next_map = IndexMap::new();
+            map.iter()
+                .try_for_each(|(key, nested_schema)| -> Result<()> {
+                    let nested_path = path.append(key);
+                    let value = get_in_schema(
+                        backend,
+                        nested_schema,
+                        root,
+                        nested_path,
+                        depth + 1,
+                        missing,
+                        raw,
+                    )?;
+                    if let Some(value) = value {
+                        next_map.insert(key.clone(), value);
+                    }
+                    Ok(())
+                })?;
+            Ok(Some(Value::Object(next_map)))
+        }
+        // if schema has a default, values are merged over it
+        Schema::Default(leaf, default) => {
+            if raw {
+                return get_or_missing(get_in_leaf(backend, leaf, root, path, depth), missing);
+            }
+            // merge the whole leaf value, so defaults apply to keys missing in the source
+            let value = match get_in_leaf(backend, leaf, root, path.take(depth), depth) {
+                Ok(value) => default.clone().deep_merge(value),
+                Err(err) => {
+                    if err.is_not_found() && missing == Missing::Default {
+                        default.clone()
+                    } else {
+                        return get_or_missing(Err(err), missing);
+                    }
+                }
+            };
+            get_in_value(path.skip(depth), value).map(Some)
+        }
+        // links are filled in by the store
+        Schema::Link(_) => Ok(None),
+        leaf => get_or_missing(get_in_leaf(backend, leaf, root, path, depth), missing),
+    }
+}
+
+fn get_in_leaf(
+    backend: &dyn Backend,
+    schema: &Schema,
+    root: &path::Path,
+    path: Path,
+    depth: usize,
+) -> Result<Value> {
+    match schema {
+        // if schema is a source (file)
+        Schema::Source(source) => {
+            let source_path: path::PathBuf = root.join(path.take(depth).to_path());
+
+            // read the file as a value
+            let source_value = source.read(backend, source_path)?;
+
+            // get value within source (file) value at path
+            let value_path = path.skip(depth);
+            get_in_value(value_path, source_value)
+        }
+        // or schema is a tree of sources (files) and directories
+        Schema::Tree(source) => get_in_tree(backend, &**source, root, path, depth),
+        // or schema is an object split into a source (file) per key
+        Schema::Split(source) => {
+            let directory_path = root.join(path.take(depth).to_path());
+            get_in_split(backend, &**source, &directory_path, path.skip(depth))
+        }
+        _ => Err(Error::Unexpected),
+    }
+}
+
+fn get_or_missing(result: Result<Value>, missing: Missing) -> Result<Option<Value>> {
+    match result {
+        Err(err) => {
+            if !err.is_not_found() {
+                return Err(err);
+            }
+            match missing {
+                Missing::Error | Missing::Default => Err(err),
+                Missing::Skip => Ok(None),
+                Missing::Null => Ok(Some(Value::Null)),
+                Missing::Empty => Ok(Some(Value::Object(IndexMap::new()))),
+            }
+        }
+        Ok(value) => Ok(Some(value)),
+    }
+}
+
+// the path of the first key in an object value which is not in the directory schema
+fn find_unknown_key(schema: &Schema, path: Path, value: &Value) -> Option<Path> {
+    match (schema, value) {
+        (Schema::Directory(map), Value::Object(object)) => {
+            object
+                .iter()
+                .find_map(|(key, nested_value)| match map.get(key) {
+                    Some(nested_schema) => {
+                        find_unknown_key(nested_schema, path.append(key), nested_value)
+                    }
+                    None => Some(path.append(key)),
+                })
+        }
+        _ => None,
+    }
+}
+
+fn set_in_schema(
+    backend: &dyn Backend,
+    schema: &Schema,
+    root: &path::Path,
+    path: Path,
+    value: &Value,
+    depth: usize,
+    mode: SetMode,
+) -> Result<()> {
+    match schema {
+        // if schema is a directory, it refers to a nested value
+        Schema::Directory(map) => {
+            ensure!(
+                value.is_object(),
+                error::SetObjectValueWhenDirectory { path: path.clone() }
+            );
+
+            let object = value.as_object().unwrap();
+
+            map.iter()
+                .try_for_each(|(key, nested_schema)| -> Result<()> {
+                    let nested_path = path.append(key);
+                    match object.get(key) {
+                        Some(nested_value) => set_in_schema(
+                            backend,
+                            nested_schema,
+                            root,
+                            nested_path,
+                            nested_value,
+                            depth + 1,
+                            mode,
+                        ),
+                        None if mode == SetMode::Replace => {
+                            delete_in_schema(backend, nested_schema, root, nested_path, depth + 1)
+                        }
+                        None => Ok(()),
+                    }
+                })
+        }
+        // otherwise schema is a source (file)
+        Schema::Source(source) => {
+            let source_path: path::PathBuf = root.join(path.take(depth).to_path());
+            let value_path = path.skip(depth);
+            set_in_source(backend, &**source, source_path, value_path, value)
+        }
+        // or schema is a tree of sources (files) and directories
+        Schema::Tree(source) => set_in_tree(backend, &**source, root, path, value, depth, mode),
+        // or schema is an object split into a source (file) per key
+        Schema::Split(source) => {
+            let directory_path = root.join(path.take(depth).to_path());
+            set_in_split(backend, &**source, &directory_path, path.skip(depth), value)
+        }
+        // defaults only apply when getting values
+        Schema::Default(leaf, _) => set_in_schema(backend, leaf, root, path, value, depth, mode),
+        // links are set by the store
+        Schema::Link(_) => Ok(()),
+    }
+}
+
+fn set_in_source(
+    backend: &dyn Backend,
+    source: &dyn Source,
+    source_path: path::PathBuf,
+    value_path: Path,
+    value: &Value,
+) -> Result<()> {
+    // ensure parent directory exists, where a source (file) at the root of a store without a
+    // root path (such as in memory) has no name
+    let directory_path = source_path
+        .parent()
+        .filter(|_| source_path.file_name().is_some())
+        .context(error::SourceFileName {
+            path: source_path.clone(),
+        })?;
+    backend
+        .create_dir(directory_path)
+        .context(error::MakeDirectory {
+            path: directory_path,
+        })?;
+
+    let source_value = match source.read(backend, source_path.clone()) {
+        // otherwise default to an empty object
+        Err(ref err) if err.is_not_found() => Ok(Value::Object(IndexMap::new())),
+        result => result,
+    }?;
+
+    // set value at path
+    let next_value = set_in_value(source_value, value_path, value.clone())?;
+
+    // write new value to source (file)
+    source.write(backend, source_path, &next_value)?;
+
+    Ok(())
+}
+
+// within a tree, `path.take(depth)` is the directory we have walked into so far
+fn get_in_tree(
+    backend: &dyn Backend,
+    source: &dyn Source,
+    root: &path::Path,
+    path: Path,
+    depth: usize,
+) -> Result<Value> {
+    debug!(
+        "get_in_tree(backend, {:?}, {:?}, {:?}, {:?})",
+        source, root, path, depth
+    );
+
+    let directory_path = root.join(path.take(depth).to_path());
+
+    if path.len() == depth {
+        return read_tree(backend, source, &directory_path);
+    }
+
+    let entry_path = directory_path.join(path.skip(depth).first());
+    if backend.is_dir(&entry_path) {
+        get_in_tree(backend, source, root, path, depth + 1)
+    } else {
+        let source_value = source.read(backend, entry_path)?;
+        get_in_value(path.skip(depth + 1), source_value)
+    }
+}
+
+fn read_tree(
+    backend: &dyn Backend,
+    source: &dyn Source,
+    directory_path: &path::Path,
+) -> Result<Value> {
+    let extensions = source.extensions();
+    let mut next_map = IndexMap::new();
+    list_directory(backend, directory_path)?
+        .into_iter()
+        .try_for_each(|(file_name, entry_path)| -> Result<()> {
+            if backend.is_dir(&entry_path) {
+                let value = read_tree(backend, source, &entry_path)?;
+                next_map.insert(file_name, value);
+            } else if let Some(key) = strip_extension(&file_name, &extensions) {
+                let value = source.read(backend, directory_path.join(&key))?;
+                next_map.insert(key, value);
+            }
+            Ok(())
+        })?;
+    Ok(Value::Object(next_map))
+}
+
+fn set_in_tree(
+    backend: &dyn Backend,
+    source: &dyn Source,
+    root: &path::Path,
+    path: Path,
+    value: &Value,
+    depth: usize,
+    mode: SetMode,
+) -> Result<()> {
+    let directory_path = root.join(path.take(depth).to_path());
+
+    // if path is the directory, set each key in the directory
+    if path.len() == depth {
+        ensure!(
+            value.is_object(),
+            error::SetObjectValueWhenDirectory { path: path.clone() }
+        );
+
+        backend
+            .create_dir(&directory_path)
+            .context(error::MakeDirectory {
+                path: &directory_path,
+            })?;
+
+        let object = value.as_object().unwrap();
+
+        if mode == SetMode::Replace {
+            // remove files and directories for keys missing from the object
+            let extensions = source.extensions();
+            list_directory(backend, &directory_path)?
+                .into_iter()
+                .try_for_each(|(file_name, entry_path)| -> Result<()> {
+                    if backend.is_dir(&entry_path) {
+                        if !object.contains_key(&file_name) {
+                            remove_directory(backend, &entry_path)?;
+                        }
+                    } else if let Some(key) = strip_extension(&file_name, &extensions) {
+                        if !object.contains_key(&key) {
+                            backend
+                                .remove_file(&entry_path)
+                                .context(error::RemoveSource { path: &entry_path })?;
+                        }
+                    }
+                    Ok(())
+                })?;
+        }
+
+        return object
+            .iter()
+            .try_for_each(|(key, nested_value)| -> Result<()> {
+                set_in_tree(
+                    backend,
+                    source,
+                    root,
+                    path.append(key),
+                    nested_value,
+                    depth,
+                    mode,
+                )
+            });
+    }
+
+    // otherwise use existing directories and files, or create new ones:
+    // objects become directories and everything else becomes a file
+    let entry_path = directory_path.join(path.skip(depth).first());
+    let is_directory = if backend.is_dir(&entry_path) {
+        true
+    } else if find_file(backend, &entry_path, &source.extensions()).is_some() {
+        false
+    } else {
+        path.len() > depth + 1 || value.is_object()
+    };
+
+    if is_directory {
+        set_in_tree(backend, source, root, path, value, depth + 1, mode)
+    } else {
+        set_in_source(backend, source, entry_path, path.skip(depth + 1), value)
+    }
+}
+
+// within a split directory, each top-level key is a source (file)
+fn get_in_split(
+    backend: &dyn Backend,
+    source: &dyn Source,
+    directory_path: &path::Path,
+    path: Path,
+) -> Result<Value> {
+    debug!(
+        "get_in_split(backend, {:?}, {:?}, {:?})",
+        source, directory_path, path
+    );
+
+    if !path.is_empty() {
+        let source_value = source.read(backend, directory_path.join(path.first()))?;
+        return get_in_value(path.rest(), source_value);
+    }
+
+    let extensions = source.extensions();
+    let mut next_map = IndexMap::new();
+    list_directory(backend, directory_path)?
+        .into_iter()
+        .try_for_each(|(file_name, entry_path)| -> Result<()> {
+            if backend.is_file(&entry_path) {
+                if let Some(key) = strip_extension(&file_name, &extensions) {
+                    let value = source.read(backend, directory_path.join(&key))?;
+                    next_map.insert(key, value);
+                }
+            }
+            Ok(())
+        })?;
+    Ok(Value::Object(next_map))
+}
+
+fn set_in_split(
+    backend: &dyn Backend,
+    source: &dyn Source,
+    directory_path: &path::Path,
+    path: Path,
+    value: &Value,
+) -> Result<()> {
+    if !path.is_empty() {
+        let source_path = directory_path.join(path.first());
+        return set_in_source(backend, source, source_path, path.rest(), value);
+    }
+
+    ensure!(
+        value.is_object(),
+        error::SetObjectValueWhenDirectory { path: path.clone() }
+    );
+    let object = value.as_object().unwrap();
+
+    backend
+        .create_dir(directory_path)
+        .context(error::MakeDirectory {
+            path: directory_path,
+        })?;
+
+    // remove files for keys no longer in the object
+    let extensions = source.extensions();
+    list_directory(backend, directory_path)?
+        .into_iter()
+        .try_for_each(|(file_name, entry_path)| -> Result<()> {
+            if !backend.is_file(&entry_path) {
+                return Ok(());
+            }
+            match strip_extension(&file_name, &extensions) {
+                Some(ref key) if !object.contains_key(key) => backend
+                    .remove_file(&entry_path)
+                    .context(error::RemoveSource { path: entry_path }),
+                _ => Ok(()),
+            }
+        })?;
+
+    object
+        .iter()
+        .try_for_each(|(key, nested_value)| -> Result<()> {
+            source.write(backend, directory_path.join(key), nested_value)
+        })
+}
+
+fn delete_in_schema(
+    backend: &dyn Backend,
+    schema: &Schema,
+    root: &path::Path,
+    path: Path,
+    depth: usize,
+) -> Result<()> {
+    let leaf_path = root.join(path.take(depth).to_path());
+    match schema {
+        Schema::Directory(map) => map
+            .iter()
+            .try_for_each(|(key, nested_schema)| -> Result<()> {
+                delete_in_schema(backend, nested_schema, root, path.append(key), depth + 1)
+            }),
+        Schema::Source(source) => delete_in_source(backend, &**source, leaf_path, path.skip(depth)),
+        Schema::Tree(source) => {
+            // walk into existing directories within the tree
+            let mut depth = depth;
+            while depth < path.len() && backend.is_dir(&root.join(path.take(depth + 1).to_path())) {
+                depth += 1;
+            }
+            if depth == path.len() {
+                remove_directory(backend, &root.join(path.to_path()))
+            } else {
+                let source_path = root.join(path.take(depth + 1).to_path());
+                delete_in_source(backend, &**source, source_path, path.skip(depth + 1))
+            }
+        }
+        Schema::Split(source) => {
+            if depth == path.len() {
+                remove_directory(backend, &leaf_path)
+            } else {
+                let source_path = leaf_path.join(path.skip(depth).first());
+                delete_in_source(backend, &**source, source_path, path.skip(depth + 1))
+            }
+        }
+        Schema::Default(leaf, _) => delete_in_schema(backend, leaf, root, path, depth),
+        // deleting a link leaves the linked value
+        Schema::Link(_) => Ok(()),
+    }
+}
+
+fn delete_in_source(
+    backend: &dyn Backend,
+    source: &dyn Source,
+    source_path: path::PathBuf,
+    value_path: Path,
+) -> Result<()> {
+    // if path is the whole source (file), remove the file
+    if value_path.is_empty() {
+        let file_path = source.file_path(backend, source_path);
+        return match backend.remove_file(&file_path) {
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+            result => result.context(error::RemoveSource { path: file_path }),
+        };
+    }
+
+    let source_value = match source.read(backend, source_path.clone()) {
+        Err(ref err) if err.is_not_found() => return Ok(()),
+        result => result?,
+    };
+    let next_value = delete_in_value(source_value, value_path);
+    source.write(backend, source_path, &next_value)
+}
+
+fn remove_directory(backend: &dyn Backend, directory_path: &path::Path) -> Result<()> {
+    match backend.remove_dir(directory_path) {
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result.context(error::RemoveDirectory {
+            path: directory_path,
+        }),
+    }
+}
+
+// the paths of keys in `value` which are not in `next_value`
+fn removed_paths(path: Path, value: &Value, next_value: &Value) -> Vec<Path> {
+    match (value, next_value) {
+        (Value::Object(map), Value::Object(next_map)) => map
+            .iter()
+            .flat_map(|(key, nested_value)| match next_map.get(key) {
+                Some(next_nested_value) => {
+                    removed_paths(path.append(key), nested_value, next_nested_value)
+                }
+                None => vec![path.append(key)],
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn get_in_value(path: Path, value: Value) -> Result<Value> {
+    if path.is_empty() {
+        return Ok(value);
+    }
+    match value {
+        Value::Object(object) => {
+            let key = path.first();
+            let next_path = path.rest();
+            let next_value = object
+                .get(key)
+                .context(error::GetSchema { path: path.clone() })?;
+            get_in_value(next_path, next_value.clone())
+        }
+        _ => Ok(value),
+    }
+}
+
+fn find_in_value<'a>(value: &'a Value, path: &Path) -> Option<&'a Value> {
+    path.iter()
+        .try_fold(value, |value, key| value.as_object()?.get(key))
+}
+
+// `value` with `next_value_at_path` set at the full `path`, keeping sibling keys and replacing any
+// non-object along the way with an object
+fn set_in_value(value: Value, path: Path, next_value_at_path: Value) -> Result<Value> {
+    if path.is_empty() {
+        return Ok(next_value_at_path);
+    }
+
+    let mut next_map = match value {
+        Value::Object(map) => map,
+        _ => IndexMap::new(),
+    };
+    let next_key = path.first().to_string();
+    let nested_value = next_map.get(&next_key).cloned().unwrap_or(Value::Null);
+    let next_nested_value = set_in_value(nested_value, path.rest(), next_value_at_path)?;
+    next_map.insert(next_key, next_nested_value);
+    Ok(Value::Object(next_map))
+}
+
+fn delete_in_value(value: Value, path: Path) -> Value {
+    match value {
+        Value::Object(map) => {
+            let key = path.first();
+            let next_path = path.rest();
+            Value::Object(
+                map.into_iter()
+                    .filter_map(|(next_key, nested_value)| {
+                        if &next_key != key {
+                            Some((next_key, nested_value))
+                        } else if next_path.is_empty() {
+                            None
+                        } else {
+                            Some((next_key, delete_in_value(nested_value, next_path.clone())))
+                        }
+                    })
+                    .collect(),
+            )
+        }
+        _ => value,
+    }
+}
@@ -0,0 +1,60 @@
+use std::sync::Arc;
+
+use snafu::ensure;
+
+use super::Store;
+use crate::error::{self, Result};
+use crate::path::Path;
+
+impl Store {
+    // the mounted store serving `path`, with the path within the mounted store
+    pub(super) fn find_mount(&self, path: &Path) -> Option<(&Store, Path)> {
+        self.mounts
+            .iter()
+            .filter(|(mount_path, _)| path.starts_with(mount_path))
+            .max_by_key(|(mount_path, _)| mount_path.len())
+            .map(|(mount_path, store)| (&**store, path.skip(mount_path.len())))
+    }
+
+    // the mounted `store` at `mount_path`, with the hooks of this store rather than its own
+    pub(super) fn mounted_store(&self, store: &Store, mount_path: &Path) -> Store {
+        Store {
+            schema: store.schema.clone(),
+            root: store.root.clone(),
+            backend: Arc::clone(&store.backend),
+            missing: store.missing,
+            mode: store.mode,
+            journal: self.journal.as_ref().map(|(journal_root, journal_path)| {
+                let journal_path: Vec<&String> =
+                    journal_path.iter().chain(mount_path.iter()).collect();
+                (journal_root.clone(), (&journal_path).into())
+            }),
+            actor: self.actor.clone(),
+            middleware: self.middleware.clone(),
+            interpolate: self.interpolate,
+            references: Some(self.references_at(mount_path)),
+            mounts: store.mounts.clone(),
+            #[cfg(feature = "git")]
+            commit_message: None,
+        }
+    }
+
+    // snapshots can't include mounted stores, since their sources (files) are outside the root
+    pub(super) fn ensure_unmounted(&self, path: &Path) -> Result<()> {
+        let path = self.resolve_links(path.clone())?;
+        ensure!(
+            self.find_mount(&path).is_none() && self.mounts_under(&path).is_empty(),
+            error::SnapshotMount { path }
+        );
+        Ok(())
+    }
+
+    // the mounted stores below `path`, with the mount paths relative to `path`
+    pub(super) fn mounts_under(&self, path: &Path) -> Vec<(Path, &Arc<Store>)> {
+        self.mounts
+            .iter()
+            .filter(|(mount_path, _)| mount_path.len() > path.len() && mount_path.starts_with(path))
+            .map(|(mount_path, store)| (mount_path.skip(path.len()), store))
+            .collect()
+    }
+}
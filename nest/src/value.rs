@@ -1,3 +1,5 @@
+use std::mem;
+
 use indexmap::IndexMap;
 
 /// Represents any valid Nest value.
@@ -33,4 +35,25 @@ impl Value {
             _ => None,
         }
     }
+
+    /// Deep merge `other` over this value: objects are merged key-wise, anything else is replaced.
+    pub fn deep_merge(self, other: Value) -> Value {
+        match (self, other) {
+            (Value::Object(mut map), Value::Object(other_map)) => {
+                other_map
+                    .into_iter()
+                    .for_each(|(key, other_value)| match map.get_mut(&key) {
+                        Some(value) => {
+                            let prev_value = mem::replace(value, Value::Null);
+                            *value = prev_value.deep_merge(other_value);
+                        }
+                        None => {
+                            map.insert(key, other_value);
+                        }
+                    });
+                Value::Object(map)
+            }
+            (_, other) => other,
+        }
+    }
 }
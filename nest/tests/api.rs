@@ -5,8 +5,6 @@ use std::convert::TryInto;
 use assert_fs::prelude::*;
 use serde_json::json;
 
-use nest;
-
 mod common;
 
 #[test]
@@ -118,6 +116,52 @@ fn set_simple() {
     temp.close().unwrap();
 }
 
+#[test]
+fn set_nested() {
+    common::setup();
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("hello/world.json")
+        .write_str(r#"{ "nest": 0, "egg": { "size": 1, "color": "white" } }"#)
+        .unwrap();
+
+    let schema = json!({ "hello": { "world": "json" } }).try_into().unwrap();
+    let store = nest::Store::new(temp.path(), schema);
+
+    // a path more than one key into a source keeps its siblings
+    assert_eq!(
+        store
+            .set(&["hello", "world", "egg", "size"], &json!(2).into())
+            .unwrap(),
+        (),
+    );
+    assert_eq!(
+        store.get(&["hello", "world"]).unwrap(),
+        json!({ "nest": 0, "egg": { "size": 2, "color": "white" } }).into(),
+    );
+
+    // and creates any objects along the way, replacing non-objects
+    assert_eq!(
+        store
+            .set(
+                &["hello", "world", "nest", "chick", "age"],
+                &json!(1).into()
+            )
+            .unwrap(),
+        (),
+    );
+    assert_eq!(
+        store.get(&["hello", "world"]).unwrap(),
+        json!({
+            "nest": { "chick": { "age": 1 } },
+            "egg": { "size": 2, "color": "white" }
+        })
+        .into(),
+    );
+
+    temp.close().unwrap();
+}
+
 #[test]
 fn set_from_empty() {
     common::setup();
@@ -175,3 +219,491 @@ fn set_from_empty() {
     temp_3.child("hello/world.json").assert(expected);
     temp_3.close().unwrap();
 }
+
+#[test]
+fn special_keys() {
+    common::setup();
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("v1.2.json")
+        .write_str(r#"{ "nest": 1 }"#)
+        .unwrap();
+    temp.child("hello world.json")
+        .write_str(r#"{ "nest": 2 }"#)
+        .unwrap();
+    temp.child("🐦/🐣.json")
+        .write_str(r#"{ "nest": 3 }"#)
+        .unwrap();
+
+    let schema = json!({
+        "v1.2": "json",
+        "hello world": "json",
+        "🐦": { "🐣": "json" }
+    })
+    .try_into()
+    .unwrap();
+    let store = nest::Store::new(temp.path(), schema);
+
+    assert_eq!(store.get(&["v1.2", "nest"]).unwrap(), json!(1).into());
+    assert_eq!(
+        store.get(&["hello world", "nest"]).unwrap(),
+        json!(2).into()
+    );
+    assert_eq!(store.get(&["🐦", "🐣", "nest"]).unwrap(), json!(3).into());
+
+    assert_eq!(
+        store
+            .set(
+                &[] as &[&str; 0],
+                &json!({
+                    "v1.2": { "nest": 4 },
+                    "hello world": { "nest": 5 },
+                    "🐦": { "🐣": { "nest": 6 } }
+                })
+                .into()
+            )
+            .unwrap(),
+        (),
+    );
+
+    temp.child("v1.2.json").assert("{\n  \"nest\": 4\n}\n");
+    temp.child("hello world.json")
+        .assert("{\n  \"nest\": 5\n}\n");
+    temp.child("🐦/🐣.json").assert("{\n  \"nest\": 6\n}\n");
+    assert!(!temp.child("v1.json").path().exists());
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn get_tree() {
+    common::setup();
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("posts/hello.json")
+        .write_str(r#"{ "title": "Hello" }"#)
+        .unwrap();
+    temp.child("posts/2019/world.json")
+        .write_str(r#"{ "title": "World" }"#)
+        .unwrap();
+    temp.child("posts/README.md").write_str("ignored").unwrap();
+
+    let schema = json!({ "posts": { "$tree": "json" } }).try_into().unwrap();
+    let store = nest::Store::new(temp.path(), schema);
+
+    assert_eq!(
+        store.get(&["posts"]).unwrap(),
+        json!({
+            "2019": { "world": { "title": "World" } },
+            "hello": { "title": "Hello" }
+        })
+        .into(),
+    );
+
+    assert_eq!(
+        store.get(&["posts", "2019", "world", "title"]).unwrap(),
+        json!("World").into(),
+    );
+
+    assert_eq!(
+        store
+            .sub(&["posts", "2019"])
+            .unwrap()
+            .get(&["world"])
+            .unwrap(),
+        json!({ "title": "World" }).into(),
+    );
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn set_tree() {
+    common::setup();
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("posts/hello.json")
+        .write_str(r#"{ "title": "Hello" }"#)
+        .unwrap();
+
+    let schema = json!({ "posts": { "$tree": "json" } }).try_into().unwrap();
+    let store = nest::Store::new(temp.path(), schema);
+
+    assert_eq!(
+        store
+            .set(&["posts", "hello", "title"], &json!("Hi").into())
+            .unwrap(),
+        (),
+    );
+    temp.child("posts/hello.json")
+        .assert("{\n  \"title\": \"Hi\"\n}\n");
+
+    assert_eq!(
+        store
+            .set(&["posts", "2019", "world", "title"], &json!("World").into())
+            .unwrap(),
+        (),
+    );
+    temp.child("posts/2019/world/title.json")
+        .assert("\"World\"\n");
+
+    assert_eq!(
+        store
+            .set(&["posts"], &json!({ "draft": true, "2020": {} }).into())
+            .unwrap(),
+        (),
+    );
+    temp.child("posts/draft.json").assert("true\n");
+    assert!(temp.child("posts/2020").path().is_dir());
+
+    assert_eq!(
+        store.get(&["posts"]).unwrap(),
+        json!({
+            "2019": { "world": { "title": "World" } },
+            "2020": {},
+            "draft": true,
+            "hello": { "title": "Hi" }
+        })
+        .into(),
+    );
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn get_split() {
+    common::setup();
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("posts/hello.json")
+        .write_str(r#"{ "title": "Hello" }"#)
+        .unwrap();
+    temp.child("posts/world.json")
+        .write_str(r#"{ "title": "World" }"#)
+        .unwrap();
+
+    let schema = json!({ "posts": { "$split": "json" } }).try_into().unwrap();
+    let store = nest::Store::new(temp.path(), schema);
+
+    assert_eq!(
+        store.get(&["posts"]).unwrap(),
+        json!({
+            "hello": { "title": "Hello" },
+            "world": { "title": "World" }
+        })
+        .into(),
+    );
+
+    assert_eq!(
+        store.get(&["posts", "world", "title"]).unwrap(),
+        json!("World").into(),
+    );
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn set_split() {
+    common::setup();
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("posts/hello.json")
+        .write_str(r#"{ "title": "Hello" }"#)
+        .unwrap();
+    temp.child("posts/world.json")
+        .write_str(r#"{ "title": "World" }"#)
+        .unwrap();
+
+    let schema = json!({ "posts": { "$split": "json" } }).try_into().unwrap();
+    let store = nest::Store::new(temp.path(), schema);
+
+    assert_eq!(
+        store
+            .set(&["posts", "world", "title"], &json!("Earth").into())
+            .unwrap(),
+        (),
+    );
+    temp.child("posts/world.json")
+        .assert("{\n  \"title\": \"Earth\"\n}\n");
+
+    assert_eq!(
+        store
+            .set(
+                &["posts"],
+                &json!({
+                    "hello": { "title": "Hi" },
+                    "nest": { "title": "Nest" }
+                })
+                .into()
+            )
+            .unwrap(),
+        (),
+    );
+    temp.child("posts/hello.json")
+        .assert("{\n  \"title\": \"Hi\"\n}\n");
+    temp.child("posts/nest.json")
+        .assert("{\n  \"title\": \"Nest\"\n}\n");
+    assert!(!temp.child("posts/world.json").path().exists());
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn infer_schema() {
+    common::setup();
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child(".nest.json").write_str("{}").unwrap();
+    temp.child("hello/world.json").write_str("{}").unwrap();
+    temp.child("hello/config.yml").write_str("").unwrap();
+    temp.child("settings.toml").write_str("").unwrap();
+    temp.child("README.md").write_str("").unwrap();
+    std::fs::create_dir_all(temp.child("empty").path()).unwrap();
+
+    let schema = nest::Schema::infer(temp.path()).unwrap();
+    let value: nest::Value = (&schema).into();
+
+    assert_eq!(
+        value,
+        json!({
+            "hello": {
+                "config": "yaml",
+                "world": "json"
+            },
+            "settings": "toml"
+        })
+        .into(),
+    );
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn schema_directory_with_leaf_names() {
+    common::setup();
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("plants/tree.json")
+        .write_str(r#"{ "leaves": 100 }"#)
+        .unwrap();
+    temp.child("plants/key.json").write_str("{}").unwrap();
+
+    // keys without a `$` are always a directory
+    let schema_json = json!({
+        "plants": {
+            "default": "yaml",
+            "key": "json",
+            "split": "toml",
+            "tree": "json"
+        },
+        "posts": { "$tree": "json", "$default": {} }
+    });
+    let schema: nest::Schema = schema_json.clone().try_into().unwrap();
+    let value: nest::Value = (&schema).into();
+    assert_eq!(value, schema_json.into());
+
+    let inferred_schema = nest::Schema::infer(temp.path()).unwrap();
+    let inferred_value: nest::Value = (&inferred_schema).into();
+    let reparsed_schema: nest::Schema = inferred_value.clone().try_into().unwrap();
+    assert_eq!(
+        nest::Value::from(&reparsed_schema),
+        json!({ "plants": { "key": "json", "tree": "json" } }).into()
+    );
+    assert_eq!(nest::Value::from(&reparsed_schema), inferred_value);
+
+    let store = nest::Store::new(temp.path(), schema);
+    assert_eq!(
+        store.get(&["plants", "tree", "leaves"]).unwrap(),
+        json!(100).into()
+    );
+
+    let invalid_schemas = vec![
+        json!({ "$tree": "json", "other": "json" }),
+        json!({ "$tree": "json", "$split": "json" }),
+        json!({ "$default": {} }),
+        json!({ "$unknown": "json" }),
+    ];
+    invalid_schemas.into_iter().for_each(|schema_json| {
+        let result: Result<nest::Schema, nest::Error> = schema_json.try_into();
+        match result {
+            Err(nest::Error::InvalidSchema { .. }) => {}
+            result => panic!("expected invalid schema error, got {:?}", result),
+        }
+    });
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn get_missing() {
+    common::setup();
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("hello/world.json")
+        .write_str(r#"{ "nest": true }"#)
+        .unwrap();
+
+    let schema: nest::Schema = json!({
+        "hello": {
+            "world": "json",
+            "missing": "json",
+            "other": { "$format": "json", "$default": { "nest": false } }
+        }
+    })
+    .try_into()
+    .unwrap();
+
+    let store = nest::Store::new(temp.path(), schema.clone());
+    assert!(store.get(&["hello"]).is_err());
+
+    let store = nest::Store::new(temp.path(), schema.clone()).with_missing(nest::Missing::Skip);
+    assert_eq!(
+        store.get(&["hello"]).unwrap(),
+        json!({ "world": { "nest": true } }).into(),
+    );
+    assert!(store.get(&["hello", "missing"]).is_err());
+
+    let store = nest::Store::new(temp.path(), schema.clone()).with_missing(nest::Missing::Null);
+    assert_eq!(
+        store.get(&["hello"]).unwrap(),
+        json!({ "world": { "nest": true }, "missing": null, "other": null }).into(),
+    );
+
+    let store = nest::Store::new(temp.path(), schema.clone()).with_missing(nest::Missing::Empty);
+    assert_eq!(
+        store.get(&["hello"]).unwrap(),
+        json!({ "world": { "nest": true }, "missing": {}, "other": {} }).into(),
+    );
+
+    let store = nest::Store::new(temp.path(), schema.clone()).with_missing(nest::Missing::Default);
+    assert!(store.get(&["hello"]).is_err());
+    assert_eq!(
+        store.get(&["hello", "other", "nest"]).unwrap(),
+        json!(false).into(),
+    );
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn get_default() {
+    common::setup();
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("hello/world.json")
+        .write_str(r#"{ "nest": { "egg": "🐣" }, "other": [1] }"#)
+        .unwrap();
+
+    let schema = json!({
+        "hello": {
+            "world": {
+                "$format": "json",
+                "$default": {
+                    "nest": { "egg": "🥚", "size": 1 },
+                    "other": [0, 0],
+                    "extra": true
+                }
+            }
+        }
+    })
+    .try_into()
+    .unwrap();
+    let store = nest::Store::new(temp.path(), schema);
+
+    assert_eq!(
+        store.get(&["hello", "world"]).unwrap(),
+        json!({
+            "nest": { "egg": "🐣", "size": 1 },
+            "other": [1],
+            "extra": true
+        })
+        .into(),
+    );
+    assert_eq!(
+        store.get(&["hello", "world", "nest", "size"]).unwrap(),
+        json!(1).into(),
+    );
+
+    assert_eq!(
+        store.get_raw(&["hello", "world"]).unwrap(),
+        json!({ "nest": { "egg": "🐣" }, "other": [1] }).into(),
+    );
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn set_replace() {
+    common::setup();
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("hello/world.json")
+        .write_str(r#"{ "nest": true }"#)
+        .unwrap();
+    temp.child("hello/other.json")
+        .write_str(r#"{ "other": true }"#)
+        .unwrap();
+
+    let schema: nest::Schema = json!({ "hello": { "world": "json", "other": "json" } })
+        .try_into()
+        .unwrap();
+
+    let store = nest::Store::new(temp.path(), schema.clone());
+    assert_eq!(
+        store
+            .set(&["hello"], &json!({ "world": { "nest": false } }).into())
+            .unwrap(),
+        (),
+    );
+    assert!(temp.child("hello/other.json").path().exists());
+
+    let store = nest::Store::new(temp.path(), schema).with_set_mode(nest::SetMode::Replace);
+    assert_eq!(
+        store
+            .set(&["hello"], &json!({ "world": { "nest": true } }).into())
+            .unwrap(),
+        (),
+    );
+    temp.child("hello/world.json")
+        .assert("{\n  \"nest\": true\n}\n");
+    assert!(!temp.child("hello/other.json").path().exists());
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn set_unknown_key() {
+    common::setup();
+
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let schema = json!({ "hello": { "world": "json" } }).try_into().unwrap();
+    let store = nest::Store::new(temp.path(), schema).with_set_mode(nest::SetMode::Replace);
+
+    match store.set(
+        &["hello"],
+        &json!({ "world": { "nest": true }, "unknown": true }).into(),
+    ) {
+        Err(nest::Error::SetUnknownKey { path }) => assert_eq!(path.to_string(), "hello/unknown"),
+        result => panic!("expected unknown key error, got {:?}", result),
+    }
+    assert!(!temp.child("hello/world.json").path().exists());
+
+    // unknown keys are ignored when merging
+    let store = store.with_set_mode(nest::SetMode::Merge);
+    assert_eq!(
+        store
+            .set(
+                &["hello"],
+                &json!({ "world": { "nest": true }, "unknown": true }).into(),
+            )
+            .unwrap(),
+        (),
+    );
+    assert_eq!(
+        store.get(&["hello"]).unwrap(),
+        json!({ "world": { "nest": true } }).into(),
+    );
+
+    temp.close().unwrap();
+}
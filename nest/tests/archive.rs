@@ -0,0 +1,71 @@
+#![cfg(feature = "archive")]
+
+extern crate assert_fs;
+
+use std::convert::TryInto;
+
+use assert_fs::prelude::*;
+use serde_json::json;
+
+use nest::backend::Archive;
+use nest::{self, Error};
+
+mod common;
+
+#[test]
+fn archive() {
+    common::setup();
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    let export_temp = assert_fs::TempDir::new().unwrap();
+    temp.child("hello/world.json")
+        .write_str(r#"{ "nest": "🐣" }"#)
+        .unwrap();
+    temp.child("notes/2019/october.yaml")
+        .write_str("tree: dinosaur\n")
+        .unwrap();
+
+    let schema: nest::Schema = json!({
+        "hello": {
+            "world": "json"
+        },
+        "notes": { "$tree": "yaml" }
+    })
+    .try_into()
+    .unwrap();
+    let store = nest::Store::new(temp.path(), schema.clone());
+
+    ["nest.tar", "nest.tar.gz", "nest.zip"]
+        .iter()
+        .for_each(|file_name| {
+            let archive_path = export_temp.child(file_name);
+            store.export_archive(archive_path.path()).unwrap();
+
+            let archive_store = nest::Store::new("", schema.clone())
+                .with_backend(Archive::open(archive_path.path()).unwrap());
+            assert_eq!(
+                archive_store.get(&[] as &[&str; 0]).unwrap(),
+                store.get(&[] as &[&str; 0]).unwrap()
+            );
+            assert_eq!(
+                archive_store.get(&["notes", "2019", "october"]).unwrap(),
+                json!({ "tree": "dinosaur" }).into()
+            );
+
+            assert!(archive_store
+                .set(&["hello", "world", "nest"], &json!("🐥").into())
+                .is_err());
+            assert_eq!(
+                archive_store.get(&["hello", "world", "nest"]).unwrap(),
+                json!("🐣").into()
+            );
+        });
+
+    match store.export_archive(export_temp.child("nest.rar").path()) {
+        Err(Error::UnknownArchiveFormat { .. }) => {}
+        result => panic!("expected unknown archive format error, got {:?}", result),
+    }
+
+    temp.close().unwrap();
+    export_temp.close().unwrap();
+}
@@ -0,0 +1,60 @@
+use std::convert::TryInto;
+use std::path::PathBuf;
+
+use serde_json::json;
+
+use nest::backend::InMemory;
+use nest::{self, Error};
+
+mod common;
+
+#[test]
+fn in_memory() {
+    common::setup();
+
+    let backend = InMemory::new();
+    let schema: nest::Schema = json!({
+        "hello": {
+            "world": "json"
+        },
+        "notes": { "$tree": "yaml" }
+    })
+    .try_into()
+    .unwrap();
+    let store = nest::Store::new("/nest", schema).with_backend(backend.clone());
+
+    match store.get(&["hello", "world"]) {
+        Err(Error::ReadSource { .. }) => {}
+        result => panic!("expected read source error, got {:?}", result),
+    }
+
+    store
+        .set(&["hello", "world"], &json!({ "nest": "🐣" }).into())
+        .unwrap();
+    store
+        .set(&["notes", "2019", "october"], &json!("dinosaur").into())
+        .unwrap();
+
+    assert_eq!(
+        backend.files().keys().cloned().collect::<Vec<PathBuf>>(),
+        vec![
+            PathBuf::from("/nest/hello/world.json"),
+            PathBuf::from("/nest/notes/2019/october.yaml"),
+        ]
+    );
+    assert_eq!(
+        backend.files()[&PathBuf::from("/nest/hello/world.json")],
+        b"{\n  \"nest\": \"\xF0\x9F\x90\xA3\"\n}\n".to_vec()
+    );
+    assert_eq!(
+        store.get(&["notes"]).unwrap(),
+        json!({ "2019": { "october": "dinosaur" } }).into()
+    );
+
+    store.delete(&["notes", "2019"]).unwrap();
+    assert_eq!(backend.files().len(), 1);
+    match store.get(&["notes", "2019"]) {
+        Err(Error::ReadSource { .. }) => {}
+        result => panic!("expected read source error, got {:?}", result),
+    }
+}
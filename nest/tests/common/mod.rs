@@ -1,6 +1,6 @@
-use std::sync::{Once, ONCE_INIT};
+use std::sync::Once;
 
-static INIT: Once = ONCE_INIT;
+static INIT: Once = Once::new();
 
 pub fn setup() {
     INIT.call_once(|| {
@@ -0,0 +1,77 @@
+extern crate assert_fs;
+
+use std::convert::TryInto;
+
+use assert_fs::prelude::*;
+use serde_json::json;
+
+use nest::{self, Change};
+
+mod common;
+
+#[test]
+fn diff_values() {
+    common::setup();
+
+    let from = json!({ "a": 1, "b": { "c": true, "d": [1] }, "e": "removed" }).into();
+    let to = json!({ "a": 2, "b": { "c": true, "d": [1, 2] }, "f": "added" }).into();
+
+    assert_eq!(
+        nest::diff(&from, &to),
+        vec![
+            Change::Modify {
+                path: (&["a"]).into(),
+                from: json!(1).into(),
+                to: json!(2).into(),
+            },
+            Change::Modify {
+                path: (&["b", "d"]).into(),
+                from: json!([1]).into(),
+                to: json!([1, 2]).into(),
+            },
+            Change::Remove {
+                path: (&["e"]).into(),
+                value: json!("removed").into(),
+            },
+            Change::Add {
+                path: (&["f"]).into(),
+                value: json!("added").into(),
+            },
+        ],
+    );
+
+    assert_eq!(nest::diff(&from, &from), vec![]);
+}
+
+#[test]
+fn diff_stores() {
+    common::setup();
+
+    let staging = assert_fs::TempDir::new().unwrap();
+    staging
+        .child("hello/world.json")
+        .write_str(r#"{ "nest": "🐣" }"#)
+        .unwrap();
+
+    let production = assert_fs::TempDir::new().unwrap();
+    production
+        .child("hello/world.json")
+        .write_str(r#"{ "nest": "🐥" }"#)
+        .unwrap();
+
+    let schema: nest::Schema = json!({ "hello": { "world": "json" } }).try_into().unwrap();
+    let staging_store = nest::Store::new(staging.path(), schema.clone());
+    let production_store = nest::Store::new(production.path(), schema);
+
+    assert_eq!(
+        staging_store.diff(&production_store).unwrap(),
+        vec![Change::Modify {
+            path: (&["hello", "world", "nest"]).into(),
+            from: json!("🐣").into(),
+            to: json!("🐥").into(),
+        }],
+    );
+
+    staging.close().unwrap();
+    production.close().unwrap();
+}
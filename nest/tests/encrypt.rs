@@ -0,0 +1,148 @@
+#![cfg(feature = "encryption")]
+
+extern crate assert_fs;
+
+use std::convert::TryInto;
+use std::fs::read_to_string;
+
+use assert_fs::prelude::*;
+use serde_json::json;
+
+use nest::{self, Error, Value};
+
+mod common;
+
+#[test]
+fn encrypt() {
+    common::setup();
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    let key_file = temp.child(".key");
+    key_file
+        .write_str("correct horse battery staple\n")
+        .unwrap();
+    let next_key_file = temp.child(".next-key");
+    next_key_file.write_str("another passphrase").unwrap();
+
+    let schema_json = json!({
+        "secrets": {
+            "$format": "yaml",
+            "$encrypt": "aes-gcm",
+            "$key": key_file.path().to_str().unwrap()
+        }
+    });
+    let schema: nest::Schema = schema_json.clone().try_into().unwrap();
+    assert_eq!(Value::from(&schema), schema_json.into());
+
+    let store = nest::Store::new(temp.path(), schema);
+    store
+        .set(&["secrets"], &json!({ "password": "hunter2" }).into())
+        .unwrap();
+
+    let file_string = read_to_string(temp.child("secrets.yaml.enc").path()).unwrap();
+    assert!(file_string.starts_with("nest:aes-gcm:"));
+    assert!(!file_string.contains("hunter2"));
+    assert_eq!(
+        store.get(&["secrets", "password"]).unwrap(),
+        json!("hunter2").into()
+    );
+
+    let next_schema = store.rekey(next_key_file.path()).unwrap();
+    match store.get(&["secrets"]) {
+        Err(Error::Decrypt { .. }) => {}
+        result => panic!("expected decrypt error, got {:?}", result),
+    }
+    assert!(!temp.child(".secrets.yaml.enc.nest-restore").path().exists());
+
+    // the key file is replaced in the schema
+    assert_eq!(
+        Value::from(&next_schema),
+        json!({
+            "secrets": {
+                "$format": "yaml",
+                "$encrypt": "aes-gcm",
+                "$key": next_key_file.path().to_str().unwrap()
+            }
+        })
+        .into()
+    );
+    let next_store = nest::Store::new(temp.path(), next_schema);
+    assert_eq!(
+        next_store.get(&["secrets"]).unwrap(),
+        json!({ "password": "hunter2" }).into()
+    );
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn encrypt_values() {
+    common::setup();
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    let key_file = temp.child(".key");
+    key_file
+        .write_str("correct horse battery staple\n")
+        .unwrap();
+
+    let schema: nest::Schema = json!({
+        "secrets": {
+            "$format": "yaml",
+            "$encrypt": "aes-gcm-values",
+            "$key": key_file.path().to_str().unwrap()
+        }
+    })
+    .try_into()
+    .unwrap();
+
+    let store = nest::Store::new(temp.path(), schema);
+    store
+        .set(
+            &["secrets"],
+            &json!({ "username": "dinosaur", "password": "hunter2" }).into(),
+        )
+        .unwrap();
+
+    let file_value: serde_yaml::Value =
+        serde_yaml::from_str(&read_to_string(temp.child("secrets.yaml").path()).unwrap()).unwrap();
+    let username = file_value["username"].as_str().unwrap().to_owned();
+    let password = file_value["password"].as_str().unwrap().to_owned();
+    assert!(username.starts_with("ENC[aes-gcm,"));
+    assert!(password.starts_with("ENC[aes-gcm,"));
+    assert_eq!(
+        store.get(&["secrets", "password"]).unwrap(),
+        json!("hunter2").into()
+    );
+
+    // unchanged values keep their ciphertext
+    store
+        .set(&["secrets", "password"], &json!("correct horse").into())
+        .unwrap();
+    let file_value: serde_yaml::Value =
+        serde_yaml::from_str(&read_to_string(temp.child("secrets.yaml").path()).unwrap()).unwrap();
+    assert_eq!(file_value["username"].as_str().unwrap(), username);
+    assert_ne!(file_value["password"].as_str().unwrap(), password);
+    assert_eq!(
+        store.get(&["secrets"]).unwrap(),
+        json!({ "username": "dinosaur", "password": "correct horse" }).into()
+    );
+
+    // every value in a source (file) has the same salt
+    let salt = |value: &str| value.split(',').nth(1).unwrap().to_owned();
+    let password = file_value["password"].as_str().unwrap().to_owned();
+    assert_eq!(salt(&username), salt(&password));
+
+    // values are bound to their path, so can't be swapped
+    temp.child("secrets.yaml")
+        .write_str(&format!(
+            "username: \"{}\"\npassword: \"{}\"\n",
+            password, username
+        ))
+        .unwrap();
+    match store.get(&["secrets"]) {
+        Err(Error::Decrypt { .. }) => {}
+        result => panic!("expected decrypt error, got {:?}", result),
+    }
+
+    temp.close().unwrap();
+}
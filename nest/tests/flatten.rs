@@ -0,0 +1,130 @@
+extern crate assert_fs;
+
+use std::convert::TryInto;
+use std::env;
+use std::fs::read_to_string;
+
+use assert_fs::prelude::*;
+use serde_json::json;
+
+use nest::{self, Path, Value};
+
+mod common;
+
+#[test]
+fn flatten() {
+    common::setup();
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("hello/world.json")
+        .write_str(r#"{ "nest": "🐣", "eggs": [1, 2], "empty": {} }"#)
+        .unwrap();
+    temp.child("hello/other.json")
+        .write_str(r#"{"x":1}"#)
+        .unwrap();
+    temp.child("notes/2019/october.json")
+        .write_str(r#"{ "tree": "dinosaur" }"#)
+        .unwrap();
+
+    let schema: nest::Schema = json!({
+        "hello": {
+            "world": "json",
+            "other": "json"
+        },
+        "notes": { "$tree": "json" }
+    })
+    .try_into()
+    .unwrap();
+    let store = nest::Store::new(temp.path(), schema);
+
+    let pairs = store.flatten(&[] as &[&str; 0]).unwrap();
+    assert_eq!(
+        pairs,
+        vec![
+            (Path::parse("hello/other/x"), json!(1).into()),
+            (Path::parse("hello/world/nest"), json!("🐣").into()),
+            (Path::parse("hello/world/eggs"), json!([1, 2]).into()),
+            (Path::parse("hello/world/empty"), json!({}).into()),
+            (
+                Path::parse("notes/2019/october/tree"),
+                json!("dinosaur").into()
+            ),
+        ]
+    );
+    assert_eq!(
+        store.flatten(&["notes"]).unwrap(),
+        vec![(
+            Path::parse("notes/2019/october/tree"),
+            json!("dinosaur").into()
+        )]
+    );
+
+    store
+        .unflatten(vec![
+            (Path::parse("hello/world/nest"), Value::from(json!("🐥"))),
+            (
+                Path::parse("notes/2019/november/tree"),
+                json!("bird").into(),
+            ),
+        ])
+        .unwrap();
+    assert_eq!(
+        read_to_string(temp.child("hello/world.json").path()).unwrap(),
+        "{\n  \"nest\": \"🐥\",\n  \"eggs\": [\n    1,\n    2\n  ],\n  \"empty\": {}\n}\n"
+    );
+    // only the sources (files) with leaves are written
+    assert_eq!(
+        read_to_string(temp.child("hello/other.json").path()).unwrap(),
+        r#"{"x":1}"#
+    );
+    assert_eq!(
+        read_to_string(temp.child("notes/2019/october.json").path()).unwrap(),
+        r#"{ "tree": "dinosaur" }"#
+    );
+    assert_eq!(
+        store.get(&["notes", "2019"]).unwrap(),
+        json!({
+            "october": { "tree": "dinosaur" },
+            "november": { "tree": "bird" }
+        })
+        .into()
+    );
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn flatten_raw() {
+    common::setup();
+
+    env::set_var("NEST_TEST_FLATTEN_SECRET", "hunter2");
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("config.json")
+        .write_str(r#"{ "password": "${env:NEST_TEST_FLATTEN_SECRET}" }"#)
+        .unwrap();
+
+    let schema: nest::Schema = json!({ "config": "json" }).try_into().unwrap();
+    let store = nest::Store::new(temp.path(), schema).with_interpolation();
+
+    let pairs = store.flatten(&["config"]).unwrap();
+    assert_eq!(
+        pairs,
+        vec![(
+            Path::parse("config/password"),
+            json!("${env:NEST_TEST_FLATTEN_SECRET}").into()
+        )]
+    );
+
+    store.unflatten(pairs).unwrap();
+    assert_eq!(
+        read_to_string(temp.child("config.json").path()).unwrap(),
+        "{\n  \"password\": \"${env:NEST_TEST_FLATTEN_SECRET}\"\n}\n"
+    );
+    assert_eq!(
+        store.get(&["config", "password"]).unwrap(),
+        json!("hunter2").into()
+    );
+
+    temp.close().unwrap();
+}
@@ -5,8 +5,6 @@ use std::convert::TryInto;
 use assert_fs::prelude::*;
 use serde_json::json;
 
-use nest;
-
 mod common;
 
 #[test]
@@ -85,6 +83,45 @@ nest: false
     temp.close().unwrap();
 }
 
+#[test]
+fn yml() {
+    common::setup();
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    let file = temp.child("hello/world.yml");
+
+    file.write_str(
+        r#"
+nest: true
+"#,
+    )
+    .unwrap();
+
+    let schema = json!({ "hello": { "world": "yaml" } }).try_into().unwrap();
+    let store = nest::Store::new(temp.path(), schema);
+
+    assert_eq!(
+        store.get(&["hello", "world", "nest"]).unwrap(),
+        json!(true).into(),
+    );
+
+    assert_eq!(
+        store
+            .set(&["hello", "world", "nest"], &json!(false).into())
+            .unwrap(),
+        (),
+    );
+
+    file.assert(
+        r#"---
+nest: false
+"#,
+    );
+    assert!(!temp.child("hello/world.yaml").path().exists());
+
+    temp.close().unwrap();
+}
+
 #[test]
 fn toml() {
     common::setup();
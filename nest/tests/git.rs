@@ -0,0 +1,95 @@
+#![cfg(feature = "git")]
+
+extern crate assert_fs;
+
+use std::convert::TryInto;
+
+use assert_fs::prelude::*;
+use serde_json::json;
+
+mod common;
+
+#[test]
+fn git_commits() {
+    common::setup();
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    let repo = git2::Repository::init(temp.path()).unwrap();
+    temp.child("hello/world.json")
+        .write_str(r#"{ "nest": "🐣" }"#)
+        .unwrap();
+
+    let schema: nest::Schema = json!({ "hello": { "world": "json", "other": "json" } })
+        .try_into()
+        .unwrap();
+    let store = nest::Store::new(temp.path(), schema).with_git_commits("{action} {path}");
+
+    store
+        .set(&["hello", "world", "nest"], &json!("🐥").into())
+        .unwrap();
+    store
+        .set(&["hello", "other"], &json!({ "a": 1 }).into())
+        .unwrap();
+    store.delete(&["hello", "other"]).unwrap();
+
+    let head = repo.head().unwrap().peel_to_commit().unwrap();
+    assert_eq!(head.message(), Some("delete hello/other"));
+    assert!(head
+        .tree()
+        .unwrap()
+        .get_path("hello/other.json".as_ref())
+        .is_err());
+    assert!(head
+        .tree()
+        .unwrap()
+        .get_path("hello/world.json".as_ref())
+        .is_ok());
+
+    store
+        .set(&["hello", "world", "nest"], &json!("🐔").into())
+        .unwrap();
+
+    let values: Vec<nest::Value> = store
+        .history(&["hello", "world", "nest"])
+        .unwrap()
+        .into_iter()
+        .map(|revision| revision.value)
+        .collect();
+    assert_eq!(values, vec![json!("🐔").into(), json!("🐥").into()]);
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn git_commits_only_sources() {
+    common::setup();
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    let repo = git2::Repository::init(temp.path()).unwrap();
+    temp.child("notes.txt")
+        .write_str("not in the store")
+        .unwrap();
+
+    let schema: nest::Schema = json!({ "hello": { "world": "json" } }).try_into().unwrap();
+    let store = nest::Store::new(temp.path(), schema).with_git_commits("{action} {path}");
+
+    // the root path maps to the store root, which is the whole working directory
+    store
+        .set(
+            &[] as &[&str; 0],
+            &json!({ "hello": { "world": { "nest": "🐥" } } }).into(),
+        )
+        .unwrap();
+
+    let tree = repo
+        .head()
+        .unwrap()
+        .peel_to_commit()
+        .unwrap()
+        .tree()
+        .unwrap();
+    assert!(tree.get_path("hello/world.json".as_ref()).is_ok());
+    assert!(tree.get_path("notes.txt".as_ref()).is_err());
+
+    temp.close().unwrap();
+}
@@ -0,0 +1,128 @@
+extern crate assert_fs;
+
+use std::convert::TryInto;
+use std::env;
+
+use assert_fs::prelude::*;
+use serde_json::json;
+
+use nest::{self, Error};
+
+mod common;
+
+#[test]
+fn interpolate() {
+    common::setup();
+
+    env::set_var("NEST_TEST_DATABASE_URL", "postgres://localhost/nest");
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("hello/world.json")
+        .write_str(r#"{ "nest": "🐣", "count": 2 }"#)
+        .unwrap();
+    temp.child("config.json")
+        .write_str(
+            &json!({
+                "database": "${env:NEST_TEST_DATABASE_URL}",
+                "nest": "${ref:hello/world/nest}",
+                "count": "${ref:hello/world/count}",
+                "message": "${ref:hello/world/nest} x ${ref:hello/world/count}",
+                "literal": "$${ref:hello/world/nest}",
+                "price": "$5"
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+    let schema: nest::Schema = json!({
+        "hello": {
+            "world": "json"
+        },
+        "config": "json"
+    })
+    .try_into()
+    .unwrap();
+    let store = nest::Store::new(temp.path(), schema).with_interpolation();
+
+    assert_eq!(
+        store.get(&["config"]).unwrap(),
+        json!({
+            "database": "postgres://localhost/nest",
+            "nest": "🐣",
+            "count": 2,
+            "message": "🐣 x 2",
+            "literal": "${ref:hello/world/nest}",
+            "price": "$5"
+        })
+        .into()
+    );
+    assert_eq!(
+        store.get_raw(&["config", "nest"]).unwrap(),
+        json!("${ref:hello/world/nest}").into()
+    );
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn interpolate_sub() {
+    common::setup();
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("hello/world.json")
+        .write_str(r#"{ "nest": "egg", "copy": "${ref:hello/world/nest}" }"#)
+        .unwrap();
+
+    let schema: nest::Schema = json!({ "hello": { "world": "json" } }).try_into().unwrap();
+    let store = nest::Store::new(temp.path(), schema).with_interpolation();
+
+    assert_eq!(
+        store.get(&["hello", "world", "copy"]).unwrap(),
+        json!("egg").into()
+    );
+
+    // references are from the root of the top-level store, not the sub-store
+    let sub = store.sub(&["hello"]).unwrap();
+    assert_eq!(sub.get(&["world", "copy"]).unwrap(), json!("egg").into());
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn interpolate_errors() {
+    common::setup();
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("config.json")
+        .write_str(
+            &json!({
+                "a": "${ref:config/b}",
+                "b": "${ref:config/a}",
+                "missing": "${ref:config/nothing}",
+                "unset": "${env:NEST_TEST_UNSET}"
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+    let schema: nest::Schema = json!({ "config": "json" }).try_into().unwrap();
+    let store = nest::Store::new(temp.path(), schema).with_interpolation();
+
+    match store.get(&["config", "a"]) {
+        Err(Error::ReferenceCycle { .. }) => {}
+        result => panic!("expected reference cycle error, got {:?}", result),
+    }
+    match store.get(&["config", "missing"]) {
+        Err(Error::UnresolvedReference { reference, path }) => {
+            assert_eq!(reference, "${ref:config/nothing}");
+            assert_eq!(path, (&["config", "missing"]).into());
+        }
+        result => panic!("expected unresolved reference error, got {:?}", result),
+    }
+    match store.get(&["config", "unset"]) {
+        Err(Error::UnresolvedReference { .. }) => {}
+        result => panic!("expected unresolved reference error, got {:?}", result),
+    }
+
+    temp.close().unwrap();
+}
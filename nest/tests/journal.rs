@@ -0,0 +1,121 @@
+extern crate assert_fs;
+
+use std::convert::TryInto;
+use std::fs::{read_to_string, File};
+
+use assert_fs::prelude::*;
+use serde_json::json;
+
+mod common;
+
+#[test]
+fn journal_replay() {
+    common::setup();
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("hello/world.json")
+        .write_str(r#"{ "nest": "🐣" }"#)
+        .unwrap();
+
+    let schema: nest::Schema = json!({ "hello": { "world": "json", "other": "json" } })
+        .try_into()
+        .unwrap();
+    let store = nest::Store::new(temp.path(), schema.clone())
+        .with_journal()
+        .with_actor("dinosaur");
+
+    store
+        .set(&["hello", "world", "nest"], &json!("🐥").into())
+        .unwrap();
+    store
+        .set(&["hello", "other"], &json!({ "a": 1 }).into())
+        .unwrap();
+    store.delete(&["hello", "other", "a"]).unwrap();
+
+    let journal_path = temp.child(".nest/journal");
+    let entries: Vec<serde_json::Value> = read_to_string(journal_path.path())
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[0]["action"], json!("set"));
+    assert_eq!(entries[0]["path"], json!(["hello", "world", "nest"]));
+    assert_eq!(entries[0]["old"], json!("🐣"));
+    assert_eq!(entries[0]["new"], json!("🐥"));
+    assert_eq!(entries[0]["actor"], json!("dinosaur"));
+    assert!(entries[1].get("old").is_none());
+    assert_eq!(entries[2]["action"], json!("delete"));
+    assert!(entries[2].get("new").is_none());
+
+    // the journal is hidden from values
+    assert_eq!(
+        store.get(&["hello"]).unwrap(),
+        json!({ "world": { "nest": "🐥" }, "other": {} }).into()
+    );
+
+    let other = assert_fs::TempDir::new().unwrap();
+    other
+        .child("hello/world.json")
+        .write_str(r#"{ "nest": "🐣" }"#)
+        .unwrap();
+    let other_store = nest::Store::new(other.path(), schema);
+    other_store
+        .replay(File::open(journal_path.path()).unwrap())
+        .unwrap();
+
+    assert_eq!(
+        other_store.get(&["hello"]).unwrap(),
+        store.get(&["hello"]).unwrap()
+    );
+    assert!(!other.child(".nest/journal").path().exists());
+
+    temp.close().unwrap();
+    other.close().unwrap();
+}
+
+#[test]
+fn journal_sub() {
+    common::setup();
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("hello/world.json")
+        .write_str(r#"{ "nest": "🐣" }"#)
+        .unwrap();
+
+    let schema: nest::Schema = json!({ "hello": { "world": "json" } }).try_into().unwrap();
+    let store = nest::Store::new(temp.path(), schema.clone()).with_journal();
+
+    // a sub-store appends to the journal of the store it came from, by full path
+    let sub_store = store.sub(&["hello"]).unwrap();
+    sub_store
+        .set(&["world", "nest"], &json!("🐥").into())
+        .unwrap();
+    assert!(!temp.child("hello/.nest/journal").path().exists());
+
+    let journal_path = temp.child(".nest/journal");
+    let entries: Vec<serde_json::Value> = read_to_string(journal_path.path())
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0]["path"], json!(["hello", "world", "nest"]));
+
+    let other = assert_fs::TempDir::new().unwrap();
+    other
+        .child("hello/world.json")
+        .write_str(r#"{ "nest": "🐣" }"#)
+        .unwrap();
+    let other_store = nest::Store::new(other.path(), schema);
+    other_store
+        .replay(File::open(journal_path.path()).unwrap())
+        .unwrap();
+    assert_eq!(
+        other_store.get(&["hello", "world", "nest"]).unwrap(),
+        json!("🐥").into()
+    );
+
+    temp.close().unwrap();
+    other.close().unwrap();
+}
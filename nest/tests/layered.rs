@@ -0,0 +1,104 @@
+extern crate assert_fs;
+
+use std::convert::TryInto;
+
+use assert_fs::prelude::*;
+use serde_json::json;
+
+mod common;
+
+#[test]
+fn layered() {
+    common::setup();
+
+    let base = assert_fs::TempDir::new().unwrap();
+    base.child("hello/world.json")
+        .write_str(r#"{ "nest": "🥚", "size": 1 }"#)
+        .unwrap();
+    base.child("hello/other.json")
+        .write_str(r#"{ "base": true }"#)
+        .unwrap();
+
+    let host = assert_fs::TempDir::new().unwrap();
+    host.child("hello/world.json")
+        .write_str(r#"{ "nest": "🐣" }"#)
+        .unwrap();
+
+    let schema: nest::Schema = json!({ "hello": { "world": "json", "other": "json" } })
+        .try_into()
+        .unwrap();
+    let store = nest::LayeredStore::new(vec![
+        nest::Store::new(base.path(), schema.clone()),
+        nest::Store::new(host.path(), schema.clone()),
+    ]);
+
+    assert_eq!(
+        store.get(&["hello"]).unwrap(),
+        json!({
+            "world": { "nest": "🐣", "size": 1 },
+            "other": { "base": true }
+        })
+        .into(),
+    );
+    assert_eq!(
+        store.get(&["hello", "other", "base"]).unwrap(),
+        json!(true).into(),
+    );
+
+    assert_eq!(
+        store.get_origin(&["hello", "world", "nest"]).unwrap(),
+        nest::Origin {
+            layer: 1,
+            path: host.child("hello/world.json").path().to_path_buf(),
+        },
+    );
+    assert_eq!(
+        store.get_origin(&["hello", "other", "base"]).unwrap(),
+        nest::Origin {
+            layer: 0,
+            path: base.child("hello/other.json").path().to_path_buf(),
+        },
+    );
+
+    assert_eq!(
+        store
+            .set(&["hello", "other"], &json!({ "host": true }).into())
+            .unwrap(),
+        (),
+    );
+    host.child("hello/other.json")
+        .assert("{\n  \"host\": true\n}\n");
+    assert_eq!(
+        store.get(&["hello", "other"]).unwrap(),
+        json!({ "base": true, "host": true }).into(),
+    );
+
+    base.close().unwrap();
+    host.close().unwrap();
+}
+
+#[test]
+fn layered_error() {
+    common::setup();
+
+    let base = assert_fs::TempDir::new().unwrap();
+    base.child("hello/world.json")
+        .write_str(r#"{ "nest": "🥚" }"#)
+        .unwrap();
+
+    let host = assert_fs::TempDir::new().unwrap();
+    host.child("hello/world.json").write_str("{ nest").unwrap();
+
+    let schema: nest::Schema = json!({ "hello": { "world": "json" } }).try_into().unwrap();
+    let store = nest::LayeredStore::new(vec![
+        nest::Store::new(base.path(), schema.clone()),
+        nest::Store::new(host.path(), schema.clone()),
+    ]);
+
+    // a layer which fails to parse isn't skipped
+    assert!(store.get(&["hello", "world", "nest"]).is_err());
+    assert!(store.get_origin(&["hello", "world", "nest"]).is_err());
+
+    base.close().unwrap();
+    host.close().unwrap();
+}
@@ -0,0 +1,163 @@
+extern crate assert_fs;
+
+use std::convert::TryInto;
+
+use assert_fs::prelude::*;
+use serde_json::json;
+
+use nest::{self, Error, Value};
+
+mod common;
+
+#[test]
+fn include() {
+    common::setup();
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("shared/.nest.json")
+        .write_str(
+            &json!({
+                "hello": {
+                    "world": "json"
+                },
+                "users": "yaml"
+            })
+            .to_string(),
+        )
+        .unwrap();
+    temp.child("repo/.nest.json")
+        .write_str(
+            &json!({
+                "$include": "../shared/.nest.json",
+                "users": { "$split": "json" },
+                "config": "toml"
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+    let schema = nest::Schema::from_file(temp.child("repo/.nest.json").path()).unwrap();
+    assert_eq!(
+        Value::from(&schema),
+        json!({
+            "config": "toml",
+            "hello": {
+                "world": "json"
+            },
+            "users": { "$split": "json" }
+        })
+        .into()
+    );
+
+    temp.child("shared/.nest.json")
+        .write_str(&json!({ "$include": "../repo/.nest.json" }).to_string())
+        .unwrap();
+    match nest::Schema::from_file(temp.child("repo/.nest.json").path()) {
+        Err(Error::IncludeCycle { .. }) => {}
+        result => panic!("expected include cycle error, got {:?}", result),
+    }
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn link() {
+    common::setup();
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("hello/world.json")
+        .write_str(r#"{ "nest": "🐣" }"#)
+        .unwrap();
+
+    let schema_json = json!({
+        "hello": {
+            "world": "json"
+        },
+        "alias": { "$link": "hello/world" }
+    });
+    let schema: nest::Schema = schema_json.clone().try_into().unwrap();
+    assert_eq!(Value::from(&schema), schema_json.into());
+    let store = nest::Store::new(temp.path(), schema);
+
+    assert_eq!(store.get(&["alias", "nest"]).unwrap(), json!("🐣").into());
+    assert_eq!(
+        store.get(&[] as &[&str; 0]).unwrap(),
+        json!({
+            "alias": { "nest": "🐣" },
+            "hello": { "world": { "nest": "🐣" } }
+        })
+        .into()
+    );
+
+    store.set(&["alias", "nest"], &json!("🐥").into()).unwrap();
+    assert_eq!(
+        store.get(&["hello", "world", "nest"]).unwrap(),
+        json!("🐥").into()
+    );
+    store
+        .set(
+            &[] as &[&str; 0],
+            &json!({ "alias": { "nest": "🐔" } }).into(),
+        )
+        .unwrap();
+    assert_eq!(
+        store.get(&["hello", "world", "nest"]).unwrap(),
+        json!("🐔").into()
+    );
+    assert_eq!(
+        store.get_origin(&["alias"]).unwrap(),
+        temp.child("hello/world.json").path()
+    );
+
+    let cycle_schema: nest::Schema = json!({
+        "a": { "$link": "b" },
+        "b": { "$link": "a" }
+    })
+    .try_into()
+    .unwrap();
+    let cycle_store = nest::Store::new(temp.path(), cycle_schema);
+    match cycle_store.get(&["a"]) {
+        Err(Error::LinkCycle { .. }) => {}
+        result => panic!("expected link cycle error, got {:?}", result),
+    }
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn link_sub() {
+    common::setup();
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("hello/world.json")
+        .write_str(r#"{ "nest": "🐣" }"#)
+        .unwrap();
+
+    let schema: nest::Schema = json!({
+        "hello": {
+            "world": "json",
+            "alias": { "$link": "hello/world" }
+        },
+        "other": {
+            "alias": { "$link": "hello/world" }
+        }
+    })
+    .try_into()
+    .unwrap();
+    let store = nest::Store::new(temp.path(), schema);
+
+    // links within the sub-store are relative to it
+    let sub_store = store.sub(&["hello"]).unwrap();
+    assert_eq!(
+        sub_store.get(&["alias", "nest"]).unwrap(),
+        json!("🐣").into()
+    );
+
+    // and links outside of it are an error
+    match store.sub(&["other"]) {
+        Err(Error::SubLink { path, .. }) => assert_eq!(path.to_string(), "other/alias"),
+        result => panic!("expected sub link error, got {:?}", result.map(|_| ())),
+    }
+
+    temp.close().unwrap();
+}
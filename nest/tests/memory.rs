@@ -0,0 +1,92 @@
+extern crate assert_fs;
+
+use std::convert::TryInto;
+
+use serde_json::json;
+
+use nest::{self, Value};
+
+mod common;
+
+fn schema() -> nest::Schema {
+    json!({
+        "hello": {
+            "world": "json"
+        },
+        "config": "toml",
+        "notes": { "$tree": "yaml" },
+        "users": { "$split": "hjson" }
+    })
+    .try_into()
+    .unwrap()
+}
+
+// the same changes, to compare stores
+fn change(store: &nest::Store) {
+    store
+        .set(&["hello", "world"], &json!({ "nest": "🐣" }).into())
+        .unwrap();
+    store
+        .set(&["config"], &json!({ "port": 8080, "debug": false }).into())
+        .unwrap();
+    store
+        .set(&["notes", "2019", "october"], &json!("dinosaur").into())
+        .unwrap();
+    store
+        .set(
+            &["users"],
+            &json!({ "dinosaur": { "name": "Dino" }, "bird": { "name": "Tweety" } }).into(),
+        )
+        .unwrap();
+    store
+        .merge(&["users"], &json!({ "bird": null }).into())
+        .unwrap();
+    store.delete(&["config", "debug"]).unwrap();
+}
+
+#[test]
+fn in_memory() {
+    common::setup();
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    let disk_store = nest::Store::new(temp.path(), schema());
+    let memory_store = nest::Store::in_memory(schema());
+
+    change(&disk_store);
+    change(&memory_store);
+
+    assert_eq!(memory_store.dump().unwrap(), disk_store.dump().unwrap());
+    assert_eq!(
+        memory_store.dump().unwrap().keys().collect::<Vec<_>>(),
+        vec![
+            std::path::Path::new("config.toml"),
+            std::path::Path::new("hello/world.json"),
+            std::path::Path::new("notes/2019/october.yaml"),
+            std::path::Path::new("users/dinosaur.hjson"),
+        ]
+    );
+    assert_eq!(
+        memory_store.get(&[] as &[&str; 0]).unwrap(),
+        disk_store.get(&[] as &[&str; 0]).unwrap()
+    );
+    assert_eq!(
+        memory_store.get(&["users"]).unwrap(),
+        Value::from(json!({ "dinosaur": { "name": "Dino" } }))
+    );
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn in_memory_root_source() {
+    common::setup();
+
+    let store = nest::Store::in_memory(json!("json").try_into().unwrap());
+
+    // without a root path, a source (file) at the root has no name
+    match store.set(&["nest"], &json!("🐣").into()) {
+        Err(nest::Error::SourceFileName { .. }) => {}
+        result => panic!("expected source file name error, got {:?}", result),
+    }
+    assert!(store.dump().unwrap().is_empty());
+}
@@ -0,0 +1,109 @@
+extern crate assert_fs;
+
+use std::convert::TryInto;
+use std::fs::read_to_string;
+use std::sync::{Arc, Mutex};
+
+use assert_fs::prelude::*;
+use serde_json::json;
+
+use nest::{self, Error, Path, StoreMiddleware, Value};
+
+mod common;
+
+// reverses strings on disk
+struct Reverse;
+
+impl StoreMiddleware for Reverse {
+    fn before_set(&self, _path: &Path, value: Value) -> Result<Value, Box<dyn std::error::Error>> {
+        Ok(reverse(value))
+    }
+
+    fn after_get(&self, _path: &Path, value: Value) -> Result<Value, Box<dyn std::error::Error>> {
+        Ok(reverse(value))
+    }
+}
+
+fn reverse(value: Value) -> Value {
+    match value {
+        Value::String(string) => Value::String(string.chars().rev().collect()),
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| (key, reverse(value)))
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+struct NoNulls;
+
+impl StoreMiddleware for NoNulls {
+    fn before_set(&self, path: &Path, value: Value) -> Result<Value, Box<dyn std::error::Error>> {
+        match value {
+            Value::Null => Err(format!("Can not set null at {}", path).into()),
+            value => Ok(value),
+        }
+    }
+}
+
+struct Notify(Arc<Mutex<Vec<String>>>);
+
+impl StoreMiddleware for Notify {
+    fn after_set(&self, path: &Path, _value: &Value) -> Result<(), Box<dyn std::error::Error>> {
+        self.0.lock().unwrap().push(path.to_string());
+        Ok(())
+    }
+}
+
+#[test]
+fn middleware() {
+    common::setup();
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("hello/world.json")
+        .write_str(r#"{ "nest": "🐣" }"#)
+        .unwrap();
+
+    let notified = Arc::new(Mutex::new(Vec::new()));
+    let schema: nest::Schema = json!({ "hello": { "world": "json" } }).try_into().unwrap();
+    let store = nest::Store::new(temp.path(), schema)
+        .with_middleware(NoNulls)
+        .with_middleware(Reverse)
+        .with_middleware(Notify(notified.clone()));
+
+    store
+        .set(&["hello", "world"], &json!({ "nest": "abc" }).into())
+        .unwrap();
+    assert_eq!(
+        store.get_raw(&["hello", "world", "nest"]).unwrap(),
+        json!("cba").into()
+    );
+    assert_eq!(
+        store.get(&["hello", "world", "nest"]).unwrap(),
+        json!("abc").into()
+    );
+
+    store
+        .merge(&["hello", "world"], &json!({ "other": "xyz" }).into())
+        .unwrap();
+    assert_eq!(
+        store.get(&["hello", "world"]).unwrap(),
+        json!({ "nest": "abc", "other": "xyz" }).into()
+    );
+
+    match store.set(&["hello", "world", "nest"], &Value::Null) {
+        Err(Error::Middleware { .. }) => {}
+        result => panic!("expected middleware error, got {:?}", result),
+    }
+    assert!(read_to_string(temp.child("hello/world.json").path())
+        .unwrap()
+        .contains("cba"));
+
+    assert_eq!(
+        *notified.lock().unwrap(),
+        vec!["hello/world".to_owned(), "hello/world".to_owned()]
+    );
+
+    temp.close().unwrap();
+}
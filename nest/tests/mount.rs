@@ -0,0 +1,156 @@
+extern crate assert_fs;
+
+use std::convert::TryInto;
+use std::fs::read_to_string;
+
+use assert_fs::prelude::*;
+use serde_json::json;
+
+use nest::{self, Value};
+
+mod common;
+
+#[test]
+fn mount() {
+    common::setup();
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    let secrets_temp = assert_fs::TempDir::new().unwrap();
+    temp.child("hello/world.json")
+        .write_str(r#"{ "nest": "🐣" }"#)
+        .unwrap();
+    secrets_temp
+        .child("database.json")
+        .write_str(r#"{ "password": "hunter2" }"#)
+        .unwrap();
+
+    let schema: nest::Schema = json!({
+        "hello": {
+            "world": "json"
+        }
+    })
+    .try_into()
+    .unwrap();
+    let secrets_schema: nest::Schema = json!({ "database": "json" }).try_into().unwrap();
+    let secrets_store = nest::Store::new(secrets_temp.path(), secrets_schema);
+    let store = nest::Store::new(temp.path(), schema).mount(&["secrets"], secrets_store);
+
+    assert_eq!(
+        store.get(&["secrets", "database", "password"]).unwrap(),
+        json!("hunter2").into()
+    );
+    assert_eq!(
+        store.get(&[] as &[&str; 0]).unwrap(),
+        json!({
+            "hello": { "world": { "nest": "🐣" } },
+            "secrets": { "database": { "password": "hunter2" } }
+        })
+        .into()
+    );
+
+    store
+        .set(
+            &["secrets", "database", "password"],
+            &json!("correct horse").into(),
+        )
+        .unwrap();
+    assert_eq!(
+        read_to_string(secrets_temp.child("database.json").path()).unwrap(),
+        "{\n  \"password\": \"correct horse\"\n}\n"
+    );
+
+    store
+        .set(
+            &[] as &[&str; 0],
+            &json!({
+                "hello": { "world": { "nest": "🐥" } },
+                "secrets": { "database": { "password": "battery staple" } }
+            })
+            .into(),
+        )
+        .unwrap();
+    assert_eq!(
+        read_to_string(temp.child("hello/world.json").path()).unwrap(),
+        "{\n  \"nest\": \"🐥\"\n}\n"
+    );
+    assert!(!temp.child("secrets").path().exists());
+
+    let sub = store.sub(&["secrets"]).unwrap();
+    assert_eq!(
+        sub.get(&["database"]).unwrap(),
+        json!({ "password": "battery staple" }).into()
+    );
+    assert_eq!(
+        store.get_origin(&["secrets", "database"]).unwrap(),
+        secrets_temp.child("database.json").path()
+    );
+
+    store.delete(&["secrets", "database"]).unwrap();
+    assert!(!secrets_temp.child("database.json").path().exists());
+    assert_eq!(
+        store.get(&["hello"]).unwrap(),
+        Value::from(json!({ "world": { "nest": "🐥" } }))
+    );
+
+    temp.close().unwrap();
+    secrets_temp.close().unwrap();
+}
+
+#[test]
+fn mount_hooks() {
+    common::setup();
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    let secrets_temp = assert_fs::TempDir::new().unwrap();
+    secrets_temp
+        .child("database.json")
+        .write_str(r#"{ "password": "hunter2" }"#)
+        .unwrap();
+
+    let schema: nest::Schema = json!({ "hello": { "world": "json" } }).try_into().unwrap();
+    let secrets_schema: nest::Schema = json!({ "database": "json" }).try_into().unwrap();
+    let secrets_store = nest::Store::new(secrets_temp.path(), secrets_schema).with_journal();
+    let store = nest::Store::new(temp.path(), schema)
+        .with_journal()
+        .mount(&["secrets"], secrets_store);
+
+    // changes to mounted values are journaled by this store, not the mounted store
+    store
+        .set(
+            &["secrets", "database", "password"],
+            &json!("correct horse").into(),
+        )
+        .unwrap();
+    store
+        .sub(&["secrets"])
+        .unwrap()
+        .set(&["database", "password"], &json!("battery staple").into())
+        .unwrap();
+    let paths: Vec<serde_json::Value> = read_to_string(temp.child(".nest/journal").path())
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["path"].clone())
+        .collect();
+    assert_eq!(
+        paths,
+        vec![
+            json!(["secrets", "database", "password"]),
+            json!(["secrets", "database", "password"])
+        ]
+    );
+    assert!(!secrets_temp.child(".nest/journal").path().exists());
+
+    // snapshots can't include mounted stores
+    match store.snapshot(&[] as &[&str; 0]) {
+        Err(nest::Error::SnapshotMount { .. }) => {}
+        result => panic!("expected snapshot mount error, got {:?}", result),
+    }
+    match store.snapshot(&["secrets", "database"]) {
+        Err(nest::Error::SnapshotMount { .. }) => {}
+        result => panic!("expected snapshot mount error, got {:?}", result),
+    }
+    assert!(store.snapshot(&["hello"]).is_ok());
+
+    temp.close().unwrap();
+    secrets_temp.close().unwrap();
+}
@@ -0,0 +1,131 @@
+extern crate assert_fs;
+
+use std::convert::{TryFrom, TryInto};
+
+use assert_fs::prelude::*;
+use serde_json::json;
+
+use nest::{self, PatchOperation};
+
+mod common;
+
+#[test]
+fn merge() {
+    common::setup();
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("hello/world.json")
+        .write_str(r#"{ "nest": "🐣", "egg": { "size": 1, "color": "white" } }"#)
+        .unwrap();
+    temp.child("hello/other.json")
+        .write_str(r#"{ "other": true }"#)
+        .unwrap();
+
+    let schema = json!({ "hello": { "world": "json", "other": "json" } })
+        .try_into()
+        .unwrap();
+    let store = nest::Store::new(temp.path(), schema);
+
+    assert_eq!(
+        store
+            .merge(
+                &["hello"],
+                &json!({
+                    "world": { "nest": "🐥", "egg": { "color": null } },
+                    "other": null
+                })
+                .into()
+            )
+            .unwrap(),
+        (),
+    );
+
+    temp.child("hello/world.json").assert(
+        r#"{
+  "nest": "🐥",
+  "egg": {
+    "size": 1
+  }
+}
+"#,
+    );
+    assert!(!temp.child("hello/other.json").path().exists());
+
+    assert_eq!(
+        store
+            .merge(&["hello", "other"], &json!({ "other": false }).into())
+            .unwrap(),
+        (),
+    );
+    temp.child("hello/other.json")
+        .assert("{\n  \"other\": false\n}\n");
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn patch() {
+    common::setup();
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("hello/world.json")
+        .write_str(r#"{ "nest": "🐣", "eggs": [1, 2] }"#)
+        .unwrap();
+    temp.child("hello/other.json")
+        .write_str(r#"{ "other": true }"#)
+        .unwrap();
+
+    let schema = json!({ "hello": { "world": "json", "other": "json" } })
+        .try_into()
+        .unwrap();
+    let store = nest::Store::new(temp.path(), schema);
+
+    let operations: Vec<PatchOperation> = vec![
+        json!({ "op": "test", "path": "/world/nest", "value": "🐣" }),
+        json!({ "op": "replace", "path": "/world/nest", "value": "🐥" }),
+        json!({ "op": "add", "path": "/world/eggs/-", "value": 3 }),
+        json!({ "op": "remove", "path": "/world/eggs/0" }),
+        json!({ "op": "copy", "from": "/other/other", "path": "/world/copied" }),
+        json!({ "op": "move", "from": "/other/other", "path": "/world/moved" }),
+    ]
+    .into_iter()
+    .map(|operation| PatchOperation::try_from(nest::Value::from(operation)).unwrap())
+    .collect();
+
+    assert_eq!(store.patch(&["hello"], &operations).unwrap(), ());
+
+    assert_eq!(
+        store.get(&["hello"]).unwrap(),
+        json!({
+            "world": {
+                "nest": "🐥",
+                "eggs": [2, 3],
+                "copied": true,
+                "moved": true
+            },
+            "other": {}
+        })
+        .into(),
+    );
+    // replaced keys stay where they were
+    temp.child("hello/world.json").assert(
+        "{\n  \"nest\": \"🐥\",\n  \"eggs\": [\n    2,\n    3\n  ],\n  \"copied\": true,\n  \"moved\": true\n}\n",
+    );
+
+    let failing = vec![
+        PatchOperation::Remove {
+            path: "/world/nest".into(),
+        },
+        PatchOperation::Test {
+            path: "/world/nest".into(),
+            value: json!("🐥").into(),
+        },
+    ];
+    assert!(store.patch(&["hello"], &failing).is_err());
+    assert_eq!(
+        store.get(&["hello", "world", "nest"]).unwrap(),
+        json!("🐥").into(),
+    );
+
+    temp.close().unwrap();
+}
@@ -0,0 +1,115 @@
+extern crate assert_fs;
+
+use std::convert::TryInto;
+use std::fs::read_to_string;
+
+use assert_fs::prelude::*;
+use serde_json::json;
+
+mod common;
+
+#[test]
+fn snapshot_restore() {
+    common::setup();
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("hello/world.json")
+        .write_str(r#"{ "nest": "🐣" }"#)
+        .unwrap();
+    temp.child("hello/tree/a.json").write_str("1").unwrap();
+
+    let schema: nest::Schema = json!({
+        "hello": {
+            "world": "json",
+            "tree": { "$tree": "json" }
+        }
+    })
+    .try_into()
+    .unwrap();
+    let store = nest::Store::new(temp.path(), schema);
+
+    let snapshot = store.snapshot(&["hello"]).unwrap();
+    assert_eq!(snapshot.files().len(), 2);
+
+    store
+        .set(&["hello", "world", "nest"], &json!("🐥").into())
+        .unwrap();
+    store
+        .set(&["hello", "tree", "b"], &json!(2).into())
+        .unwrap();
+    assert!(temp.child("hello/tree/b.json").path().exists());
+
+    store.restore(&snapshot).unwrap();
+
+    assert_eq!(
+        store.get(&["hello"]).unwrap(),
+        json!({ "world": { "nest": "🐣" }, "tree": { "a": 1 } }).into()
+    );
+    assert_eq!(
+        read_to_string(temp.child("hello/world.json").path()).unwrap(),
+        r#"{ "nest": "🐣" }"#
+    );
+    assert!(!temp.child("hello/tree/b.json").path().exists());
+
+    temp.close().unwrap();
+}
+
+#[cfg(feature = "archive")]
+#[test]
+fn snapshot_tar() {
+    common::setup();
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("hello/world.json")
+        .write_str(r#"{ "nest": "🐣" }"#)
+        .unwrap();
+
+    let schema: nest::Schema = json!({ "hello": { "world": "json" } }).try_into().unwrap();
+    let store = nest::Store::new(temp.path(), schema.clone());
+
+    let snapshot = store.snapshot(&["hello", "world"]).unwrap();
+    let mut tarball = Vec::new();
+    snapshot.write_tar(&mut tarball).unwrap();
+
+    let read_snapshot = nest::Snapshot::read_tar(tarball.as_slice()).unwrap();
+    assert_eq!(read_snapshot, snapshot);
+
+    let other = assert_fs::TempDir::new().unwrap();
+    let other_store = nest::Store::new(other.path(), schema);
+    other_store.restore(&read_snapshot).unwrap();
+
+    assert_eq!(
+        other_store.get(&["hello", "world", "nest"]).unwrap(),
+        json!("🐣").into()
+    );
+
+    temp.close().unwrap();
+    other.close().unwrap();
+}
+
+#[test]
+fn restore_failed() {
+    common::setup();
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("hello/world.json")
+        .write_str(r#"{ "nest": "🐣" }"#)
+        .unwrap();
+
+    let schema: nest::Schema = json!({ "hello": { "world": "json" } }).try_into().unwrap();
+    let store = nest::Store::new(temp.path(), schema.clone());
+    let snapshot = store.snapshot(&["hello"]).unwrap();
+
+    // a directory in the way of a source (file) can't be replaced
+    let other = assert_fs::TempDir::new().unwrap();
+    other.child("hello/world.json/in-the-way").touch().unwrap();
+    let other_store = nest::Store::new(other.path(), schema);
+    assert!(other_store.restore(&snapshot).is_err());
+    assert!(!other
+        .child("hello/.world.json.nest-restore")
+        .path()
+        .exists());
+
+    temp.close().unwrap();
+    other.close().unwrap();
+}
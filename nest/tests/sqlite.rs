@@ -0,0 +1,68 @@
+#![cfg(feature = "sqlite")]
+
+extern crate assert_fs;
+
+use std::convert::TryInto;
+use std::fs::read_to_string;
+
+use assert_fs::prelude::*;
+use serde_json::json;
+
+mod common;
+
+#[test]
+fn sqlite() {
+    common::setup();
+
+    let temp = assert_fs::TempDir::new().unwrap();
+    let import_temp = assert_fs::TempDir::new().unwrap();
+    let db_temp = assert_fs::TempDir::new().unwrap();
+    temp.child("hello/world.json")
+        .write_str(r#"{ "nest": "🐣", "eggs": [1, 2], "a/b": true }"#)
+        .unwrap();
+    temp.child("notes/2019/october.json")
+        .write_str(r#"{ "tree": "dinosaur", "empty": {} }"#)
+        .unwrap();
+
+    let schema: nest::Schema = json!({
+        "hello": {
+            "world": "json"
+        },
+        "notes": { "$tree": "json" }
+    })
+    .try_into()
+    .unwrap();
+    let store = nest::Store::new(temp.path(), schema.clone());
+    let db_path = db_temp.child("nest.db");
+    store.export_sqlite(db_path.path()).unwrap();
+    // exporting again replaces the table
+    store.export_sqlite(db_path.path()).unwrap();
+
+    // paths are stored as arrays of keys, since keys may contain `/`
+    let connection = rusqlite::Connection::open(db_path.path()).unwrap();
+    let path: String = connection
+        .query_row("SELECT path FROM nest WHERE value = 'true'", [], |row| {
+            row.get(0)
+        })
+        .unwrap();
+    assert_eq!(path, r#"["hello","world","a/b"]"#);
+
+    let import_store = nest::Store::new(import_temp.path(), schema);
+    import_store.import_sqlite(db_path.path()).unwrap();
+    assert_eq!(
+        import_store.get(&[] as &[&str; 0]).unwrap(),
+        json!({
+            "hello": { "world": { "nest": "🐣", "eggs": [1, 2], "a/b": true } },
+            "notes": { "2019": { "october": { "tree": "dinosaur", "empty": {} } } }
+        })
+        .into()
+    );
+    assert_eq!(
+        read_to_string(import_temp.child("hello/world.json").path()).unwrap(),
+        "{\n  \"nest\": \"🐣\",\n  \"eggs\": [\n    1,\n    2\n  ],\n  \"a/b\": true\n}\n"
+    );
+
+    temp.close().unwrap();
+    import_temp.close().unwrap();
+    db_temp.close().unwrap();
+}